@@ -0,0 +1,128 @@
+use crate::cli::ColorDepth;
+use crate::color;
+use crate::screen::{self, RawMode, Wake};
+use crate::theme;
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
+use tui::backend::{Backend, CrosstermBackend};
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::text::Text;
+use tui::widgets::{Paragraph, Widget};
+
+/// ASCII art made of several frames, played in place of the static ASCII art.
+pub struct Animation {
+    frames: Vec<Text<'static>>,
+    frame_duration: Duration,
+    duration: Option<Duration>,
+    color_depth: ColorDepth,
+}
+
+/// The most frames shown per second, faster animations would only keep the CPU busy.
+const MAX_FRAME_RATE: f32 = 120.0;
+
+impl Animation {
+    /// Returns an error if the frame rate or the duration of `options` is out of range.
+    pub fn new(
+        frames: Vec<Text<'static>>,
+        options: &theme::Animation,
+        color_depth: ColorDepth,
+    ) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        if !(options.frame_rate > 0.0 && options.frame_rate <= MAX_FRAME_RATE) {
+            return Err(invalid(format!(
+                "The frame rate of the animation must be above 0 and at most {}, not {}.",
+                MAX_FRAME_RATE, options.frame_rate
+            )));
+        }
+        let frame_duration = Duration::from_secs_f32(1.0 / options.frame_rate);
+
+        let duration = match options.duration {
+            Some(0.0) => None,
+            Some(duration) => Some(Duration::try_from_secs_f32(duration).map_err(|_| {
+                invalid(format!(
+                    "The duration of the animation must be a number of seconds, not {}.",
+                    duration
+                ))
+            })?),
+            None => Some(frame_duration * frames.len() as u32),
+        };
+
+        Ok(Animation {
+            frames,
+            frame_duration,
+            duration,
+            color_depth,
+        })
+    }
+
+    /// Returns the number of columns and rows needed to hold every frame.
+    pub fn size(&self) -> (u16, u16) {
+        self.frames.iter().fold((0, 0), |(width, height), frame| {
+            (
+                width.max(frame.width() as u16),
+                height.max(frame.height() as u16),
+            )
+        })
+    }
+
+    /// Draws `frame` with its top left corner at `x` and `y`, blanking whatever is left of the
+    /// previous frame.
+    fn draw_frame(
+        &self,
+        backend: &mut CrosstermBackend<Stdout>,
+        frame: &Text<'static>,
+        x: u16,
+        y: u16,
+    ) -> io::Result<()> {
+        let (width, height) = self.size();
+        let mut buffer = Buffer::empty(Rect::new(0, 0, width, height));
+        Paragraph::new(frame.clone()).render(buffer.area, &mut buffer);
+        color::quantize_buffer(&mut buffer, self.color_depth);
+
+        let cells = crate::buffer::visible_cells(&buffer)
+            .map(|(cell_x, cell_y, cell)| (x + cell_x, y + cell_y, cell));
+
+        backend.draw(cells)?;
+        backend.flush()
+    }
+
+    fn run(&self, backend: &mut CrosstermBackend<Stdout>, x: u16, y: u16) -> io::Result<()> {
+        let start = Instant::now();
+        let mut deadline = start;
+
+        for frame in self.frames.iter().cycle() {
+            self.draw_frame(backend, frame, x, y)?;
+
+            deadline += self.frame_duration;
+            if matches!(self.duration, Some(duration) if deadline - start >= duration) {
+                return Ok(());
+            }
+
+            // The art that was already drawn stays where it is when the terminal is resized.
+            loop {
+                match screen::wait_for_key(deadline, |_| true)? {
+                    Wake::Key => return Ok(()),
+                    Wake::Resize => (),
+                    Wake::Deadline => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loops through the frames at `x` and `y` until the configured duration has passed or a
+    /// key is pressed, leaving the last frame that was shown on screen.
+    pub fn play(&self, backend: &mut CrosstermBackend<Stdout>, x: u16, y: u16) -> io::Result<()> {
+        backend.flush()?;
+        let (cursor_x, cursor_y) = backend.get_cursor()?;
+
+        // Raw mode lets any key stop the animation, without echoing it.
+        let result = RawMode::enter().and_then(|_raw_mode| self.run(backend, x, y));
+
+        backend.set_cursor(cursor_x, cursor_y)?;
+        result
+    }
+}
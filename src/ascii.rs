@@ -1,10 +1,14 @@
+use crate::color;
+use crate::theme::{Gradient, GradientDirection, Theme, ThemeColor};
 use colored::Colorize;
 use io::Read;
-use std::fs::File;
+use std::collections::HashMap;
+use std::fs::{self, File};
 use std::io::{self, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tui::style::{Color, Style};
 use tui::text::{Span, Spans, Text};
+use unicode_width::UnicodeWidthChar;
 
 lazy_static! {
     static ref BLUE: Style = Style::default().fg(Color::Blue);
@@ -14,6 +18,371 @@ lazy_static! {
     static ref MAGENTA: Style = Style::default().fg(Color::Magenta);
     static ref WHITE: Style = Style::default().fg(Color::White);
     static ref BLACK: Style = Style::default().fg(Color::Black);
+    // Detected on first use, so fetches that show other art never read os-release.
+    static ref DETECTED_ASCII: Option<&'static str> = distro_ids()
+        .iter()
+        .find_map(|id| BUILTIN_ASCII.iter().find(|a| a.id == id))
+        .map(|a| a.id);
+}
+
+/// The 16 ANSI colors, in the order of their SGR codes, along with the names
+/// `custom_ascii.remap` knows them by.
+pub const ANSI_COLOR_NAMES: [(&str, Color); 16] = [
+    ("Black", Color::Black),
+    ("Red", Color::Red),
+    ("Green", Color::Green),
+    ("Yellow", Color::Yellow),
+    ("Blue", Color::Blue),
+    ("Magenta", Color::Magenta),
+    ("Cyan", Color::Cyan),
+    ("Gray", Color::Gray),
+    ("DarkGray", Color::DarkGray),
+    ("LightRed", Color::LightRed),
+    ("LightGreen", Color::LightGreen),
+    ("LightYellow", Color::LightYellow),
+    ("LightBlue", Color::LightBlue),
+    ("LightMagenta", Color::LightMagenta),
+    ("LightCyan", Color::LightCyan),
+    ("White", Color::White),
+];
+
+/// Returns the position of `color` in `ANSI_COLOR_NAMES`, if it is one of the 16 ANSI colors.
+fn ansi_index(color: Color) -> Option<usize> {
+    match color {
+        Color::Indexed(i) if i < 16 => Some(i as usize),
+        color => ANSI_COLOR_NAMES.iter().position(|(_, c)| *c == color),
+    }
+}
+
+/// Replaces the ANSI colors of `ascii` with the ones they're mapped to in `remap`.
+fn remap_colors(ascii: &mut Text<'static>, remap: &HashMap<String, ThemeColor>) {
+    let targets: Vec<Option<Color>> = ANSI_COLOR_NAMES
+        .iter()
+        .map(|(name, _)| {
+            remap
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, color)| color.0)
+        })
+        .collect();
+
+    let map =
+        |color: Option<Color>| color.map(|c| ansi_index(c).and_then(|i| targets[i]).unwrap_or(c));
+
+    for line in ascii.lines.iter_mut() {
+        for span in line.0.iter_mut() {
+            span.style.fg = map(span.style.fg);
+            span.style.bg = map(span.style.bg);
+        }
+    }
+}
+
+/// Colors every character of `ascii` according to its place along `gradient`.
+fn apply_gradient(ascii: &mut Text<'static>, gradient: &Gradient) {
+    let stops: Vec<(u8, u8, u8)> = gradient
+        .colors
+        .iter()
+        .map(|color| color::to_rgb(color.0, color::DEFAULT_FG))
+        .collect();
+
+    let progress = |i: usize, len: usize| match len {
+        0 | 1 => 0.0,
+        len => i as f32 / (len - 1) as f32,
+    };
+
+    let (width, height) = (ascii.width(), ascii.height());
+    for (y, line) in ascii.lines.iter_mut().enumerate() {
+        let spans: Vec<Span<'static>> = line.0.drain(..).collect();
+        let mut x = 0;
+
+        for span in spans {
+            match gradient.direction {
+                GradientDirection::Vertical => {
+                    let color = color::gradient_color(&stops, progress(y, height));
+                    line.0
+                        .push(Span::styled(span.content, span.style.fg(color)));
+                }
+                GradientDirection::Horizontal => {
+                    for c in span.content.chars() {
+                        let color = color::gradient_color(&stops, progress(x, width));
+                        line.0
+                            .push(Span::styled(c.to_string(), span.style.fg(color)));
+                        x += c.width().unwrap_or(0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Applies the color remapping and gradient of the theme to `ascii`.
+pub fn apply_theme_colors(ascii: &mut Text<'static>, theme: &Theme) {
+    if !theme.get_ascii_remap().is_empty() {
+        remap_colors(ascii, theme.get_ascii_remap());
+    }
+
+    if let Some(gradient) = theme.get_ascii_gradient() {
+        apply_gradient(ascii, gradient);
+    }
+}
+
+/// Splits `ascii` into one `(character, style)` pair per character, line by line.
+fn to_cells(ascii: &Text<'static>) -> Vec<Vec<(char, Style)>> {
+    ascii
+        .lines
+        .iter()
+        .map(|line| {
+            line.0
+                .iter()
+                .flat_map(|span| span.content.chars().map(move |c| (c, span.style)))
+                .collect()
+        })
+        .collect()
+}
+
+/// Turns cells back into text, merging neighbouring cells that share a style into one span.
+fn from_cells(cells: Vec<Vec<(char, Style)>>) -> Text<'static> {
+    let lines: Vec<Spans<'static>> = cells
+        .into_iter()
+        .map(|line| {
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            for (c, style) in line {
+                match spans.last_mut() {
+                    Some(span) if span.style == style => span.content.to_mut().push(c),
+                    _ => spans.push(Span::styled(c.to_string(), style)),
+                }
+            }
+
+            Spans::from(spans)
+        })
+        .collect();
+
+    Text::from(lines)
+}
+
+/// A cell is blank if nothing of it shows up on screen, whatever its foreground color.
+fn is_blank((c, style): &(char, Style)) -> bool {
+    c.is_whitespace() && matches!(style.bg, None | Some(Color::Reset))
+}
+
+/// Removes the blank columns at the end of every line of `ascii` and the blank lines below it.
+pub fn crop(ascii: Text<'static>) -> Text<'static> {
+    let mut cells = to_cells(&ascii);
+    for line in cells.iter_mut() {
+        let len = line
+            .iter()
+            .rposition(|cell| !is_blank(cell))
+            .map_or(0, |i| i + 1);
+        line.truncate(len);
+    }
+
+    while matches!(cells.last(), Some(line) if line.is_empty()) {
+        cells.pop();
+    }
+
+    from_cells(cells)
+}
+
+/// Returns `true` if `ascii` is colored with RGB colors.
+pub fn is_truecolor(ascii: &Text<'static>) -> bool {
+    ascii
+        .lines
+        .iter()
+        .flat_map(|line| line.0.iter())
+        .any(|span| {
+            matches!(span.style.fg, Some(Color::Rgb(..)))
+                || matches!(span.style.bg, Some(Color::Rgb(..)))
+        })
+}
+
+/// Returns the average of the colors of `cells` picked by `color`, if at least half of them
+/// have one.
+fn average_color<'a>(
+    cells: impl Iterator<Item = &'a (char, Style)>,
+    color: impl Fn(&Style) -> Option<Color>,
+    default: (u8, u8, u8),
+) -> Option<Color> {
+    let (mut count, mut total, mut sum) = (0, 0, (0u32, 0u32, 0u32));
+    for (_, style) in cells {
+        total += 1;
+        if let Some(c) = color(style).filter(|c| *c != Color::Reset) {
+            let (r, g, b) = color::to_rgb(c, default);
+            sum = (sum.0 + r as u32, sum.1 + g as u32, sum.2 + b as u32);
+            count += 1;
+        }
+    }
+
+    if count == 0 || count * 2 < total {
+        return None;
+    }
+
+    Some(Color::Rgb(
+        (sum.0 / count) as u8,
+        (sum.1 / count) as u8,
+        (sum.2 / count) as u8,
+    ))
+}
+
+/// Shrinks `ascii` down to `width` columns, keeping its proportions. Every cell of the result
+/// stands for a block of the original: it takes the most common character of the block, unless
+/// most of the block is blank, and the average of its colors.
+pub fn downscale(ascii: &Text<'static>, width: u16) -> Text<'static> {
+    let cells = to_cells(ascii);
+    let in_width = cells.iter().map(Vec::len).max().unwrap_or(0);
+    let in_height = cells.len();
+    let out_width = width as usize;
+    if out_width == 0 || in_width <= out_width {
+        return ascii.clone();
+    }
+
+    let out_height = ((in_height * out_width) as f32 / in_width as f32)
+        .round()
+        .max(1.0) as usize;
+    let range = |i: usize, out_len: usize, in_len: usize| {
+        let start = i * in_len / out_len;
+        start..((i + 1) * in_len / out_len).max(start + 1)
+    };
+
+    let blank = (' ', Style::default());
+    let (cells, blank) = (&cells, &blank);
+    let scaled = (0..out_height)
+        .map(|y| {
+            (0..out_width)
+                .map(|x| {
+                    let block: Vec<&(char, Style)> = range(y, out_height, in_height)
+                        .flat_map(|row| {
+                            range(x, out_width, in_width)
+                                .map(move |col| cells[row].get(col).unwrap_or(blank))
+                        })
+                        .collect();
+
+                    let filled: Vec<&(char, Style)> = block
+                        .iter()
+                        .copied()
+                        .filter(|cell| !is_blank(cell))
+                        .collect();
+                    if filled.len() * 2 < block.len() {
+                        return *blank;
+                    }
+
+                    let mut counts: Vec<(char, usize)> = Vec::new();
+                    for (c, _) in &filled {
+                        match counts.iter_mut().find(|(other, _)| other == c) {
+                            Some((_, n)) => *n += 1,
+                            None => counts.push((*c, 1)),
+                        }
+                    }
+
+                    let c = counts
+                        .iter()
+                        .max_by_key(|(_, n)| *n)
+                        .map_or(' ', |(c, _)| *c);
+                    let style = Style {
+                        fg: average_color(filled.iter().copied(), |s| s.fg, color::DEFAULT_FG),
+                        bg: average_color(block.iter().copied(), |s| s.bg, color::DEFAULT_BG),
+                        ..filled[0].1
+                    };
+
+                    (c, style)
+                })
+                .collect()
+        })
+        .collect();
+
+    crop(from_cells(scaled))
+}
+
+/// ASCII art embedded at build time, keyed by the `ID` a distribution uses in `/etc/os-release`.
+struct BuiltinAscii {
+    id: &'static str,
+    art: &'static [u8],
+    // Applied to art that doesn't come with its own colors.
+    color: Option<Color>,
+}
+
+const BUILTIN_ASCII: &[BuiltinAscii] = &[
+    BuiltinAscii {
+        id: "arch",
+        art: include_bytes!("../ascii/archlinux.ascii"),
+        color: None,
+    },
+    BuiltinAscii {
+        id: "fedora",
+        art: include_bytes!("../ascii/fedoralinux.ascii"),
+        color: Some(Color::Blue),
+    },
+];
+
+/// Returns the IDs of the built-in ASCII art.
+pub fn builtin_ascii_ids() -> Vec<&'static str> {
+    BUILTIN_ASCII.iter().map(|a| a.id).collect()
+}
+
+/// Returns the built-in ASCII art with the given ID, if there is one.
+pub fn get_builtin_ascii(id: &str) -> Option<Text<'static>> {
+    let builtin = BUILTIN_ASCII
+        .iter()
+        .find(|a| a.id.eq_ignore_ascii_case(id))?;
+    let text = match builtin.color {
+        Some(color) => ansi_to_tui::ansi_to_text_override_style(
+            builtin.art.to_vec(),
+            Style::default().fg(color),
+        ),
+        None => ansi_to_tui::ansi_to_text(builtin.art.to_vec()),
+    };
+
+    text.ok()
+}
+
+/// Returns the `ID` of the running distribution followed by the ones listed in `ID_LIKE`,
+/// falling back to the first word of its name if `os-release` can't be read.
+fn distro_ids() -> Vec<String> {
+    use libmacchina::traits::GeneralReadout as _;
+
+    let os_release = fs::read_to_string("/etc/os-release")
+        .or_else(|_| fs::read_to_string("/usr/lib/os-release"));
+
+    if let Ok(os_release) = os_release {
+        let value = |key: &str| {
+            os_release
+                .lines()
+                .filter_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                .map(|v| v.trim_matches(|c| c == '"' || c == '\''))
+                .next()
+                .unwrap_or_default()
+        };
+
+        return value("ID")
+            .split_whitespace()
+            .chain(value("ID_LIKE").split_whitespace())
+            .map(str::to_lowercase)
+            .collect();
+    }
+
+    libmacchina::GeneralReadout::new()
+        .distribution()
+        .ok()
+        .and_then(|name| name.split_whitespace().next().map(str::to_lowercase))
+        .into_iter()
+        .collect()
+}
+
+/// Returns the ID of the built-in ASCII art that matches the running distribution, which is
+/// only detected the first time this is called.
+pub fn detect_builtin_ascii() -> Option<&'static str> {
+    *DETECTED_ASCII
+}
+
+pub fn list_builtin_ascii() {
+    let detected = detect_builtin_ascii();
+
+    for id in builtin_ascii_ids() {
+        if Some(id) == detected {
+            println!("- {} (detected)", id.bright_green());
+        } else {
+            println!("- {}", id.bright_green());
+        }
+    }
 }
 
 pub fn list_ascii_artists() {
@@ -69,6 +438,58 @@ pub fn get_ascii_from_file(file_path: &Path) -> Result<Text<'static>, io::Error>
     Ok(ansi_to_tui::ansi_to_text(buffer).unwrap_or_default())
 }
 
+/// Returns the frames of the ASCII art at `path`, which is either a directory holding one frame
+/// per file, or a file whose frames are delimited by `separator` lines. The color of every frame
+/// is replaced by `color` if one is given.
+pub fn get_ascii_frames(
+    path: &Path,
+    separator: Option<&str>,
+    color: Option<Color>,
+) -> Result<Vec<Text<'static>>, io::Error> {
+    let to_text = |buffer: Vec<u8>| match color {
+        Some(color) => ansi_to_tui::ansi_to_text_override_style(buffer, Style::default().fg(color))
+            .unwrap_or_default(),
+        None => ansi_to_tui::ansi_to_text(buffer).unwrap_or_default(),
+    };
+
+    let read_frame = |file: &Path| match color {
+        Some(color) => get_ascii_from_file_override_color(file, color),
+        None => get_ascii_from_file(file),
+    };
+
+    if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.is_file())
+            .collect();
+        files.sort();
+
+        return files.iter().map(|file| read_frame(file)).collect();
+    }
+
+    let separator = match separator {
+        Some(separator) => separator,
+        None => return Ok(vec![read_frame(path)?]),
+    };
+
+    let content = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+    let mut frames = vec![String::new()];
+    for line in content.lines() {
+        if line.trim_end() == separator {
+            frames.push(String::new());
+        } else if let Some(frame) = frames.last_mut() {
+            frame.push_str(line);
+            frame.push('\n');
+        }
+    }
+
+    Ok(frames
+        .into_iter()
+        .filter(|frame| !frame.is_empty())
+        .map(|frame| to_text(frame.into_bytes()))
+        .collect())
+}
+
 // The following is a slightly modified
 // version of neofetch's Apple ASCII art.
 #[cfg(target_os = "macos")]
@@ -0,0 +1,64 @@
+use tui::buffer::{Buffer, Cell};
+use unicode_width::UnicodeWidthStr;
+
+/// Returns the cells of `buf` along with their column and row, leaving out the ones that are
+/// hidden behind wide characters and the ones that take up no room.
+pub fn visible_cells(buf: &Buffer) -> impl Iterator<Item = (u16, u16, &Cell)> {
+    let mut skip_n = 0;
+
+    buf.content.iter().enumerate().filter_map(move |(i, cell)| {
+        let (x, y) = buf.pos_of(i);
+        // A wide character at the end of a row doesn't hide anything on the next one.
+        if x == buf.area.left() {
+            skip_n = 0;
+        }
+
+        if skip_n > 0 {
+            skip_n -= 1;
+            return None;
+        }
+
+        let width = cell.symbol.width();
+        if width == 0 {
+            return None;
+        }
+
+        skip_n = width - 1;
+        Some((x, y, cell))
+    })
+}
+
+/// Returns the column just past the rightmost cell that isn't blank on the rows above `last_y`.
+pub fn find_widest_cell(buf: &Buffer, last_y: u16) -> u16 {
+    let area = &buf.area;
+    let mut widest: u16 = 0;
+    let empty_cell = Cell::default();
+
+    for y in 0..last_y {
+        for x in (0..area.width).rev() {
+            let current_cell = buf.get(x, y);
+            if current_cell.ne(&empty_cell) && x > widest {
+                widest = x;
+                break;
+            }
+        }
+    }
+
+    widest + 1
+}
+
+/// Returns the column and row of the last cell of `buf` that isn't blank.
+pub fn find_last_buffer_cell_index(buf: &Buffer) -> Option<(u16, u16)> {
+    let empty_cell = Cell::default();
+
+    if let Some((idx, _)) = buf
+        .content
+        .iter()
+        .enumerate()
+        .rfind(|p| !(*(p.1)).eq(&empty_cell))
+    {
+        return Some(buf.pos_of(idx));
+    }
+
+    None
+}
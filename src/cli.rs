@@ -46,7 +46,51 @@ arg_enum! {
     }
 }
 
-#[derive(StructOpt, Debug, Serialize, Deserialize)]
+arg_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub enum ColorMode {
+        Auto,
+        Always,
+        Never
+    }
+}
+
+impl ColorMode {
+    /// Returns `true` if the output should be colored, `Auto` colors the output
+    /// only if it goes to a terminal and the NO_COLOR environment variable is not set.
+    pub fn is_enabled(&self, is_tty: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                is_tty && !matches!(std::env::var_os("NO_COLOR"), Some(v) if !v.is_empty())
+            }
+        }
+    }
+}
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub enum ColorDepth {
+        // 24-bit colors
+        TrueColor,
+        // The xterm 256-color palette
+        Ansi256,
+        // The 16 ANSI colors
+        Ansi16
+    }
+}
+
+arg_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum ExportFormat {
+        Svg,
+        Html,
+        Png
+    }
+}
+
+#[derive(StructOpt, Debug, Clone, Serialize, Deserialize)]
 #[structopt(author = AUTHORS, about = ABOUT)]
 #[serde(default, deny_unknown_fields)]
 pub struct Opt {
@@ -97,6 +141,14 @@ pub struct Opt {
     )]
     pub theme: Option<String>,
 
+    #[structopt(
+        long = "ascii",
+        value_name = "name",
+        number_of_values = 1,
+        help = "Use the ASCII art called <name>, picking one at random if given several times"
+    )]
+    pub ascii: Option<Vec<String>>,
+
     #[structopt(
         long = "export-config",
         short = "e",
@@ -130,12 +182,66 @@ pub struct Opt {
     #[serde(skip_serializing, skip_deserializing)]
     pub ascii_artists: bool,
 
+    #[structopt(
+        long = "list-ascii",
+        help = "Lists the built-in ASCII art and the ASCII art found in macchina/ascii"
+    )]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub list_ascii: bool,
+
     #[structopt(
         long = "interface",
         short = "i",
         help = "Specify the network interface for the LocalIP readout"
     )]
     pub interface: Option<String>,
+
+    #[structopt(
+        long = "watch",
+        short = "w",
+        value_name = "seconds",
+        help = "Redraws volatile readouts every <seconds> until q or Ctrl-C is pressed",
+        conflicts_with = "doctor",
+        parse(try_from_str = parse_interval)
+    )]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub watch: Option<u64>,
+
+    #[structopt(
+        long = "interactive",
+        short = "I",
+        help = "Browse readouts and themes interactively, then save them to the config file",
+        conflicts_with_all = &["doctor", "watch"]
+    )]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub interactive: bool,
+
+    #[structopt(
+        long = "color",
+        value_name = "when",
+        possible_values = & ColorMode::variants(),
+        case_insensitive = true,
+        help = "Specify when to color the output: auto, always or never"
+    )]
+    pub color: Option<ColorMode>,
+
+    #[structopt(
+        long = "color-depth",
+        value_name = "depth",
+        possible_values = & ColorDepth::variants(),
+        case_insensitive = true,
+        help = "Reduce the colors to truecolor, ansi256 or ansi16 instead of detecting what the terminal supports"
+    )]
+    pub color_depth: Option<ColorDepth>,
+
+    #[structopt(
+        long = "export",
+        number_of_values = 2,
+        value_names = &["format", "file"],
+        help = "Exports the fetch to <file> instead of printing it, <format> is svg, html or png"
+    )]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub export: Option<Vec<String>>,
 }
 
 impl Default for Opt {
@@ -150,14 +256,53 @@ impl Default for Opt {
             long_kernel: true,
             list_themes: false,
             ascii_artists: false,
+            list_ascii: false,
             config: None,
             theme: None,
+            ascii: None,
             show: None,
             interface: None,
+            watch: None,
+            interactive: false,
+            color: None,
+            color_depth: None,
+            export: None,
         }
     }
 }
 
+impl Opt {
+    /// Returns the format and destination given to `--export`, or an error if the format is
+    /// unknown.
+    pub fn export_target(&self) -> Result<Option<(ExportFormat, std::path::PathBuf)>, clap::Error> {
+        let export = match self.export.as_ref() {
+            Some(export) => export,
+            None => return Ok(None),
+        };
+
+        match export[0].parse::<ExportFormat>() {
+            Ok(format) => Ok(Some((format, std::path::PathBuf::from(&export[1])))),
+            Err(_) => Err(clap::Error::with_description(
+                &format!(
+                    "Invalid export format \"{}\", expected one of: {}",
+                    export[0],
+                    ExportFormat::variants().join(", ").to_lowercase()
+                ),
+                clap::ErrorKind::InvalidValue,
+            )),
+        }
+    }
+}
+
+/// Parses the `--watch` interval, which has to be at least a second.
+fn parse_interval(seconds: &str) -> Result<u64, String> {
+    match seconds.parse::<u64>() {
+        Ok(0) => Err(String::from("the interval must be at least 1 second")),
+        Ok(seconds) => Ok(seconds),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 #[allow(dead_code)]
 pub fn build_cli() -> App<'static, 'static> {
     Opt::clap()
@@ -0,0 +1,614 @@
+use crate::ascii::ANSI_COLOR_NAMES;
+use crate::cli::ColorDepth;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use tui::buffer::Buffer;
+use tui::style::Color;
+
+/// The CSS named colors, which are mostly the X11 ones.
+const CSS_COLORS: &[(&str, (u8, u8, u8))] = &[
+    ("aliceblue", (240, 248, 255)),
+    ("antiquewhite", (250, 235, 215)),
+    ("aqua", (0, 255, 255)),
+    ("aquamarine", (127, 255, 212)),
+    ("azure", (240, 255, 255)),
+    ("beige", (245, 245, 220)),
+    ("bisque", (255, 228, 196)),
+    ("black", (0, 0, 0)),
+    ("blanchedalmond", (255, 235, 205)),
+    ("blue", (0, 0, 255)),
+    ("blueviolet", (138, 43, 226)),
+    ("brown", (165, 42, 42)),
+    ("burlywood", (222, 184, 135)),
+    ("cadetblue", (95, 158, 160)),
+    ("chartreuse", (127, 255, 0)),
+    ("chocolate", (210, 105, 30)),
+    ("coral", (255, 127, 80)),
+    ("cornflowerblue", (100, 149, 237)),
+    ("cornsilk", (255, 248, 220)),
+    ("crimson", (220, 20, 60)),
+    ("cyan", (0, 255, 255)),
+    ("darkblue", (0, 0, 139)),
+    ("darkcyan", (0, 139, 139)),
+    ("darkgoldenrod", (184, 134, 11)),
+    ("darkgray", (169, 169, 169)),
+    ("darkgreen", (0, 100, 0)),
+    ("darkgrey", (169, 169, 169)),
+    ("darkkhaki", (189, 183, 107)),
+    ("darkmagenta", (139, 0, 139)),
+    ("darkolivegreen", (85, 107, 47)),
+    ("darkorange", (255, 140, 0)),
+    ("darkorchid", (153, 50, 204)),
+    ("darkred", (139, 0, 0)),
+    ("darksalmon", (233, 150, 122)),
+    ("darkseagreen", (143, 188, 143)),
+    ("darkslateblue", (72, 61, 139)),
+    ("darkslategray", (47, 79, 79)),
+    ("darkslategrey", (47, 79, 79)),
+    ("darkturquoise", (0, 206, 209)),
+    ("darkviolet", (148, 0, 211)),
+    ("deeppink", (255, 20, 147)),
+    ("deepskyblue", (0, 191, 255)),
+    ("dimgray", (105, 105, 105)),
+    ("dimgrey", (105, 105, 105)),
+    ("dodgerblue", (30, 144, 255)),
+    ("firebrick", (178, 34, 34)),
+    ("floralwhite", (255, 250, 240)),
+    ("forestgreen", (34, 139, 34)),
+    ("fuchsia", (255, 0, 255)),
+    ("gainsboro", (220, 220, 220)),
+    ("ghostwhite", (248, 248, 255)),
+    ("gold", (255, 215, 0)),
+    ("goldenrod", (218, 165, 32)),
+    ("gray", (128, 128, 128)),
+    ("green", (0, 128, 0)),
+    ("greenyellow", (173, 255, 47)),
+    ("grey", (128, 128, 128)),
+    ("honeydew", (240, 255, 240)),
+    ("hotpink", (255, 105, 180)),
+    ("indianred", (205, 92, 92)),
+    ("indigo", (75, 0, 130)),
+    ("ivory", (255, 255, 240)),
+    ("khaki", (240, 230, 140)),
+    ("lavender", (230, 230, 250)),
+    ("lavenderblush", (255, 240, 245)),
+    ("lawngreen", (124, 252, 0)),
+    ("lemonchiffon", (255, 250, 205)),
+    ("lightblue", (173, 216, 230)),
+    ("lightcoral", (240, 128, 128)),
+    ("lightcyan", (224, 255, 255)),
+    ("lightgoldenrodyellow", (250, 250, 210)),
+    ("lightgray", (211, 211, 211)),
+    ("lightgreen", (144, 238, 144)),
+    ("lightgrey", (211, 211, 211)),
+    ("lightpink", (255, 182, 193)),
+    ("lightsalmon", (255, 160, 122)),
+    ("lightseagreen", (32, 178, 170)),
+    ("lightskyblue", (135, 206, 250)),
+    ("lightslategray", (119, 136, 153)),
+    ("lightslategrey", (119, 136, 153)),
+    ("lightsteelblue", (176, 196, 222)),
+    ("lightyellow", (255, 255, 224)),
+    ("lime", (0, 255, 0)),
+    ("limegreen", (50, 205, 50)),
+    ("linen", (250, 240, 230)),
+    ("magenta", (255, 0, 255)),
+    ("maroon", (128, 0, 0)),
+    ("mediumaquamarine", (102, 205, 170)),
+    ("mediumblue", (0, 0, 205)),
+    ("mediumorchid", (186, 85, 211)),
+    ("mediumpurple", (147, 112, 219)),
+    ("mediumseagreen", (60, 179, 113)),
+    ("mediumslateblue", (123, 104, 238)),
+    ("mediumspringgreen", (0, 250, 154)),
+    ("mediumturquoise", (72, 209, 204)),
+    ("mediumvioletred", (199, 21, 133)),
+    ("midnightblue", (25, 25, 112)),
+    ("mintcream", (245, 255, 250)),
+    ("mistyrose", (255, 228, 225)),
+    ("moccasin", (255, 228, 181)),
+    ("navajowhite", (255, 222, 173)),
+    ("navy", (0, 0, 128)),
+    ("oldlace", (253, 245, 230)),
+    ("olive", (128, 128, 0)),
+    ("olivedrab", (107, 142, 35)),
+    ("orange", (255, 165, 0)),
+    ("orangered", (255, 69, 0)),
+    ("orchid", (218, 112, 214)),
+    ("palegoldenrod", (238, 232, 170)),
+    ("palegreen", (152, 251, 152)),
+    ("paleturquoise", (175, 238, 238)),
+    ("palevioletred", (219, 112, 147)),
+    ("papayawhip", (255, 239, 213)),
+    ("peachpuff", (255, 218, 185)),
+    ("peru", (205, 133, 63)),
+    ("pink", (255, 192, 203)),
+    ("plum", (221, 160, 221)),
+    ("powderblue", (176, 224, 230)),
+    ("purple", (128, 0, 128)),
+    ("rebeccapurple", (102, 51, 153)),
+    ("red", (255, 0, 0)),
+    ("rosybrown", (188, 143, 143)),
+    ("royalblue", (65, 105, 225)),
+    ("saddlebrown", (139, 69, 19)),
+    ("salmon", (250, 128, 114)),
+    ("sandybrown", (244, 164, 96)),
+    ("seagreen", (46, 139, 87)),
+    ("seashell", (255, 245, 238)),
+    ("sienna", (160, 82, 45)),
+    ("silver", (192, 192, 192)),
+    ("skyblue", (135, 206, 235)),
+    ("slateblue", (106, 90, 205)),
+    ("slategray", (112, 128, 144)),
+    ("slategrey", (112, 128, 144)),
+    ("snow", (255, 250, 250)),
+    ("springgreen", (0, 255, 127)),
+    ("steelblue", (70, 130, 180)),
+    ("tan", (210, 180, 140)),
+    ("teal", (0, 128, 128)),
+    ("thistle", (216, 191, 216)),
+    ("tomato", (255, 99, 71)),
+    ("turquoise", (64, 224, 208)),
+    ("violet", (238, 130, 238)),
+    ("wheat", (245, 222, 179)),
+    ("white", (255, 255, 255)),
+    ("whitesmoke", (245, 245, 245)),
+    ("yellow", (255, 255, 0)),
+    ("yellowgreen", (154, 205, 50)),
+];
+
+/// The colors used by xterm for the 16 ANSI colors, which also make up the first 16 entries of
+/// the 256-color palette.
+const ANSI_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The foreground color of cells that don't specify one.
+pub const DEFAULT_FG: (u8, u8, u8) = ANSI_COLORS[7];
+
+/// The background color of cells that don't specify one, unless the theme sets another.
+pub const DEFAULT_BG: (u8, u8, u8) = ANSI_COLORS[0];
+
+/// Returns the RGB value of `color`, `Color::Reset` resolves to `default`.
+pub fn to_rgb(color: Color, default: (u8, u8, u8)) -> (u8, u8, u8) {
+    match color {
+        Color::Reset => default,
+        Color::Black => ANSI_COLORS[0],
+        Color::Red => ANSI_COLORS[1],
+        Color::Green => ANSI_COLORS[2],
+        Color::Yellow => ANSI_COLORS[3],
+        Color::Blue => ANSI_COLORS[4],
+        Color::Magenta => ANSI_COLORS[5],
+        Color::Cyan => ANSI_COLORS[6],
+        Color::Gray => ANSI_COLORS[7],
+        Color::DarkGray => ANSI_COLORS[8],
+        Color::LightRed => ANSI_COLORS[9],
+        Color::LightGreen => ANSI_COLORS[10],
+        Color::LightYellow => ANSI_COLORS[11],
+        Color::LightBlue => ANSI_COLORS[12],
+        Color::LightMagenta => ANSI_COLORS[13],
+        Color::LightCyan => ANSI_COLORS[14],
+        Color::White => ANSI_COLORS[15],
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(i) => indexed_to_rgb(i),
+    }
+}
+
+/// Returns the RGB value of an entry of the xterm 256-color palette.
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match index {
+        0..=15 => ANSI_COLORS[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            (
+                LEVELS[(i / 36) as usize],
+                LEVELS[(i / 6 % 6) as usize],
+                LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+/// Parses `#rgb` and `#rrggbb`.
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |i: usize, len: usize| u8::from_str_radix(&hex[i * len..(i + 1) * len], 16);
+    match hex.len() {
+        3 => {
+            let short = |i| channel(i, 1).map(|c| c * 17);
+            Some(Color::Rgb(short(0).ok()?, short(1).ok()?, short(2).ok()?))
+        }
+        6 => Some(Color::Rgb(
+            channel(0, 2).ok()?,
+            channel(1, 2).ok()?,
+            channel(2, 2).ok()?,
+        )),
+        _ => None,
+    }
+}
+
+/// Parses the inside of `rgb(r, g, b)`.
+fn parse_rgb(channels: &str) -> Option<Color> {
+    let channels: Vec<u8> = channels
+        .split(',')
+        .map(|c| c.trim().parse().ok())
+        .collect::<Option<_>>()?;
+
+    match channels[..] {
+        [r, g, b] => Some(Color::Rgb(r, g, b)),
+        _ => None,
+    }
+}
+
+/// Parses a color written as one of the names `tui` knows (e.g. `LightBlue`), a CSS color name
+/// (e.g. `dodgerblue`), `#1e90ff`, `rgb(30, 144, 255)` or `ansi:208`. The names of the 16 ANSI
+/// colors take precedence over the CSS ones, so that `Blue` follows the terminal's palette.
+pub fn parse_color(color: &str) -> Result<Color, String> {
+    let color = color.trim();
+    let lowercase = color.to_lowercase();
+
+    let parsed = if lowercase == "reset" {
+        Some(Color::Reset)
+    } else if let Some((_, ansi)) = ANSI_COLOR_NAMES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(color))
+    {
+        Some(*ansi)
+    } else if let Some(hex) = color.strip_prefix('#') {
+        parse_hex(hex)
+    } else if let Some(channels) = lowercase
+        .strip_prefix("rgb(")
+        .and_then(|c| c.strip_suffix(')'))
+    {
+        parse_rgb(channels)
+    } else if let Some(index) = lowercase.strip_prefix("ansi:") {
+        index.trim().parse().ok().map(Color::Indexed)
+    } else {
+        CSS_COLORS
+            .iter()
+            .find(|(name, _)| *name == lowercase)
+            .map(|(_, (r, g, b))| Color::Rgb(*r, *g, *b))
+    };
+
+    parsed.ok_or_else(|| {
+        format!(
+            "invalid color \"{}\", expected a color name, \"#rrggbb\", \"rgb(r, g, b)\" or \"ansi:N\"",
+            color
+        )
+    })
+}
+
+/// Returns the color found at `t` (between 0 and 1) along a gradient going through `stops`.
+pub fn gradient_color(stops: &[(u8, u8, u8)], t: f32) -> Color {
+    if stops.len() == 1 {
+        let (r, g, b) = stops[0];
+        return Color::Rgb(r, g, b);
+    }
+
+    let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let i = (position.floor() as usize).min(stops.len() - 2);
+    let f = position - i as f32;
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+    let (from, to) = (stops[i], stops[i + 1]);
+
+    Color::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+/// Returns the colors that pixels are reduced to at the given depth, along with their RGB
+/// values. `ColorDepth::TrueColor` needs no palette.
+pub fn palette(depth: ColorDepth) -> Vec<(Color, (u8, u8, u8))> {
+    let colors: Vec<Color> = match depth {
+        ColorDepth::TrueColor => return Vec::new(),
+        // The 16 ANSI colors are left out since terminals tend to redefine them.
+        ColorDepth::Ansi256 => (16..=255).map(Color::Indexed).collect(),
+        ColorDepth::Ansi16 => vec![
+            Color::Black,
+            Color::Red,
+            Color::Green,
+            Color::Yellow,
+            Color::Blue,
+            Color::Magenta,
+            Color::Cyan,
+            Color::Gray,
+            Color::DarkGray,
+            Color::LightRed,
+            Color::LightGreen,
+            Color::LightYellow,
+            Color::LightBlue,
+            Color::LightMagenta,
+            Color::LightCyan,
+            Color::White,
+        ],
+    };
+
+    colors
+        .into_iter()
+        .map(|color| (color, to_rgb(color, DEFAULT_FG)))
+        .collect()
+}
+
+/// Returns the index of the entry of `palette` that is closest to `rgb`, using the "redmean"
+/// approximation of how far apart colors look.
+pub fn nearest(palette: &[(Color, (u8, u8, u8))], rgb: [f32; 3]) -> usize {
+    let distance = |(r, g, b): (u8, u8, u8)| {
+        let red_mean = (rgb[0] + r as f32) / 2.0;
+        let dr = rgb[0] - r as f32;
+        let dg = rgb[1] - g as f32;
+        let db = rgb[2] - b as f32;
+        (2.0 + red_mean / 256.0) * dr * dr
+            + 4.0 * dg * dg
+            + (2.0 + (255.0 - red_mean) / 256.0) * db * db
+    };
+
+    (0..palette.len())
+        .min_by(|a, b| distance(palette[*a].1).total_cmp(&distance(palette[*b].1)))
+        .unwrap()
+}
+
+/// Returns the number of colors the terminfo entry of `term` declares, if it can be found.
+fn terminfo_colors(term: &str) -> Option<u32> {
+    // Where ncurses looks for compiled entries, in order.
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    dirs.extend(env::var_os("TERMINFO").map(PathBuf::from));
+    dirs.extend(dirs::home_dir().map(|home| home.join(".terminfo")));
+    if let Some(list) = env::var_os("TERMINFO_DIRS") {
+        dirs.extend(env::split_paths(&list).filter(|dir| !dir.as_os_str().is_empty()));
+    }
+    dirs.extend(
+        [
+            "/etc/terminfo",
+            "/lib/terminfo",
+            "/usr/share/terminfo",
+            "/usr/lib/terminfo",
+        ]
+        .iter()
+        .map(PathBuf::from),
+    );
+
+    // Entries are filed under their first letter, or its hexadecimal code on macOS.
+    let first = term.chars().next()?;
+    let data = dirs.iter().find_map(|dir| {
+        fs::read(dir.join(first.to_string()).join(term))
+            .or_else(|_| fs::read(dir.join(format!("{:x}", first as u32)).join(term)))
+            .ok()
+    })?;
+
+    parse_terminfo_colors(&data)
+}
+
+/// Returns the number of colors declared by a compiled terminfo entry.
+fn parse_terminfo_colors(data: &[u8]) -> Option<u32> {
+    // See term(5): a header of six shorts, the names, the booleans, then the numbers, the
+    // colors being the 14th of them.
+    const COLORS: usize = 13;
+    let short = |i: usize| Some(u16::from_le_bytes([*data.get(i)?, *data.get(i + 1)?]) as usize);
+    let number_size = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+
+    let (names_size, bools, numbers) = (short(2)?, short(4)?, short(6)?);
+    if numbers <= COLORS {
+        return None;
+    }
+
+    let numbers_start = 12 + names_size + bools;
+    let start = numbers_start + numbers_start % 2 + COLORS * number_size;
+    let bytes = data.get(start..start + number_size)?;
+    let colors = match number_size {
+        2 => i16::from_le_bytes([bytes[0], bytes[1]]) as i32,
+        _ => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+    };
+
+    (colors > 0).then_some(colors as u32)
+}
+
+/// Guesses how many colors the terminal supports from `COLORTERM`, `TERM` and the terminfo
+/// entry of the latter. Terminals that can't be identified are assumed to support 256 colors,
+/// which nearly all of them do, rather than risk printing colors they can't show.
+pub fn detect_color_depth() -> ColorDepth {
+    guess_color_depth(
+        &env::var("COLORTERM").unwrap_or_default(),
+        &env::var("TERM").unwrap_or_default(),
+    )
+}
+
+/// Does the work of `detect_color_depth` for the given values of `COLORTERM` and `TERM`.
+fn guess_color_depth(colorterm: &str, term: &str) -> ColorDepth {
+    let colorterm = colorterm.to_lowercase();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return ColorDepth::TrueColor;
+    }
+
+    if term.is_empty() {
+        return ColorDepth::Ansi256;
+    }
+
+    if term.ends_with("-direct") || term == "xterm-kitty" || term == "xterm-ghostty" {
+        return ColorDepth::TrueColor;
+    }
+
+    match terminfo_colors(term) {
+        Some(colors) if colors >= 1 << 24 => ColorDepth::TrueColor,
+        Some(colors) if colors >= 256 => ColorDepth::Ansi256,
+        Some(_) => ColorDepth::Ansi16,
+        None if term.ends_with("256color") => ColorDepth::Ansi256,
+        None if term == "linux" || term.starts_with("vt") => ColorDepth::Ansi16,
+        None => ColorDepth::Ansi256,
+    }
+}
+
+/// Returns the color closest to `color` among the ones available at `depth`, where `palette`
+/// is `palette(depth)`.
+fn quantize_color(color: Color, depth: ColorDepth, palette: &[(Color, (u8, u8, u8))]) -> Color {
+    let to_palette = |color: Color| {
+        let (r, g, b) = to_rgb(color, DEFAULT_FG);
+        palette[nearest(palette, [r as f32, g as f32, b as f32])].0
+    };
+
+    match (color, depth) {
+        (_, ColorDepth::TrueColor) => color,
+        (Color::Rgb(..), ColorDepth::Ansi256) => to_palette(color),
+        (Color::Indexed(i), ColorDepth::Ansi16) if i < 16 => ANSI_COLOR_NAMES[i as usize].1,
+        (Color::Rgb(..), ColorDepth::Ansi16) | (Color::Indexed(_), ColorDepth::Ansi16) => {
+            to_palette(color)
+        }
+        _ => color,
+    }
+}
+
+/// Reduces the colors of every cell of `buf` to the ones available at `depth`.
+pub fn quantize_buffer(buf: &mut Buffer, depth: ColorDepth) {
+    if depth == ColorDepth::TrueColor {
+        return;
+    }
+
+    let palette = palette(depth);
+    for cell in buf.content.iter_mut() {
+        cell.fg = quantize_color(cell.fg, depth, &palette);
+        cell.bg = quantize_color(cell.bg, depth, &palette);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_color("#1e90ff"), Ok(Color::Rgb(30, 144, 255)));
+        assert_eq!(parse_color("#1E90FF"), Ok(Color::Rgb(30, 144, 255)));
+        assert_eq!(parse_color("#f80"), Ok(Color::Rgb(255, 136, 0)));
+        assert!(parse_color("#1e90f").is_err());
+        assert!(parse_color("#gggggg").is_err());
+    }
+
+    #[test]
+    fn parses_rgb_colors() {
+        assert_eq!(
+            parse_color("rgb(30, 144, 255)"),
+            Ok(Color::Rgb(30, 144, 255))
+        );
+        assert_eq!(parse_color("RGB(0,0,0)"), Ok(Color::Rgb(0, 0, 0)));
+        assert!(parse_color("rgb(30, 144)").is_err());
+        assert!(parse_color("rgb(256, 0, 0)").is_err());
+    }
+
+    #[test]
+    fn parses_ansi_indices() {
+        assert_eq!(parse_color("ansi:208"), Ok(Color::Indexed(208)));
+        assert_eq!(parse_color("ansi: 0"), Ok(Color::Indexed(0)));
+        assert!(parse_color("ansi:256").is_err());
+        assert!(parse_color("ansi:").is_err());
+    }
+
+    #[test]
+    fn prefers_ansi_names_over_css_names() {
+        assert_eq!(parse_color("Blue"), Ok(Color::Blue));
+        assert_eq!(parse_color("lightblue"), Ok(Color::LightBlue));
+        assert_eq!(parse_color("reset"), Ok(Color::Reset));
+        assert_eq!(parse_color("dodgerblue"), Ok(Color::Rgb(30, 144, 255)));
+    }
+
+    #[test]
+    fn names_the_invalid_color() {
+        let error = parse_color("bleu").unwrap_err();
+        assert!(error.contains("\"bleu\""), "{}", error);
+    }
+
+    /// Returns a compiled terminfo entry declaring `colors` colors, whose numbers are
+    /// `number_size` bytes long.
+    fn terminfo(number_size: usize, colors: i32) -> Vec<u8> {
+        let magic: u16 = if number_size == 2 { 0o432 } else { 0o1036 };
+        // An odd length, so that the numbers are preceded by a padding byte.
+        let names = b"test|A test terminal\0";
+
+        let mut data = Vec::new();
+        for short in [magic, names.len() as u16, 0, 14, 0, 0] {
+            data.extend_from_slice(&short.to_le_bytes());
+        }
+        data.extend_from_slice(names);
+        data.push(0);
+
+        for number in [-1; 13].iter().chain([colors].iter()) {
+            data.extend_from_slice(&number.to_le_bytes()[..number_size]);
+        }
+
+        data
+    }
+
+    #[test]
+    fn reads_colors_from_terminfo() {
+        assert_eq!(parse_terminfo_colors(&terminfo(2, 256)), Some(256));
+        assert_eq!(parse_terminfo_colors(&terminfo(4, 1 << 24)), Some(1 << 24));
+        assert_eq!(parse_terminfo_colors(&terminfo(2, -1)), None);
+        assert_eq!(parse_terminfo_colors(&terminfo(2, 256)[1..]), None);
+    }
+
+    #[test]
+    fn guesses_color_depth() {
+        assert_eq!(
+            guess_color_depth("truecolor", "xterm"),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(guess_color_depth("24BIT", ""), ColorDepth::TrueColor);
+        assert_eq!(guess_color_depth("", "xterm-direct"), ColorDepth::TrueColor);
+        assert_eq!(guess_color_depth("", "xterm-kitty"), ColorDepth::TrueColor);
+        assert_eq!(guess_color_depth("", "linux"), ColorDepth::Ansi16);
+        assert_eq!(guess_color_depth("", ""), ColorDepth::Ansi256);
+        assert_eq!(
+            guess_color_depth("", "unknown-terminal"),
+            ColorDepth::Ansi256
+        );
+    }
+
+    #[test]
+    fn quantizes_buffer_to_ansi16() {
+        let mut buf = Buffer::empty(tui::layout::Rect::new(0, 0, 4, 1));
+        buf.get_mut(0, 0).set_fg(Color::Rgb(250, 10, 10));
+        buf.get_mut(1, 0).set_fg(Color::Indexed(4));
+        buf.get_mut(2, 0).set_fg(Color::Indexed(46));
+        buf.get_mut(3, 0)
+            .set_fg(Color::Magenta)
+            .set_bg(Color::Rgb(0, 0, 0));
+
+        quantize_buffer(&mut buf, ColorDepth::Ansi16);
+
+        let colors: Vec<(Color, Color)> = buf.content.iter().map(|c| (c.fg, c.bg)).collect();
+        assert_eq!(
+            colors,
+            vec![
+                (Color::LightRed, Color::Reset),
+                (Color::Blue, Color::Reset),
+                (Color::LightGreen, Color::Reset),
+                (Color::Magenta, Color::Black),
+            ]
+        );
+    }
+}
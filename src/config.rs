@@ -1,4 +1,5 @@
 use crate::cli::Opt;
+use crate::data;
 use dirs::config_dir;
 use std::io::Read;
 use std::path::{Path, PathBuf};
@@ -26,32 +27,80 @@ impl Opt {
         }
     }
 
+    /// Returns the path of the configuration file that macchina reads, i.e. the one specified by
+    /// the MACCHINA_CONF environment variable or `$XDG_CONFIG_HOME/macchina/macchina.toml`
+    pub fn config_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("MACCHINA_CONF") {
+            return Some(PathBuf::from(path));
+        }
+
+        let mut path = config_dir()?;
+        path.push(PKG_NAME);
+        path.push(format!("{}.toml", PKG_NAME));
+
+        if !Path::exists(&path) && cfg!(target_os = "macos") {
+            if let Ok(home) = std::env::var("HOME") {
+                let fallback = PathBuf::from(home)
+                    .join(".config")
+                    .join(PKG_NAME)
+                    .join(format!("{}.toml", PKG_NAME));
+
+                if Path::exists(&fallback) {
+                    return Some(fallback);
+                }
+            }
+        }
+
+        Some(path)
+    }
+
     /// Reads config file specified by MACCHINA_CONF environment variable
     pub fn from_config() -> Result<Opt, &'static str> {
-        if let Some(path) = std::env::var_os("MACCHINA_CONF") {
-            return Opt::from_config_file(&path);
-        } else if let Some(mut path) = config_dir() {
-            path.push(PKG_NAME);
-            path.push(format!("{}.toml", PKG_NAME));
-            if Path::exists(&path) {
+        if let Some(path) = Opt::config_path() {
+            if std::env::var_os("MACCHINA_CONF").is_some() || Path::exists(&path) {
                 return Opt::from_config_file(&path);
-            } else if cfg!(target_os = "macos") {
-                if let Ok(home) = std::env::var("HOME") {
-                    let path = PathBuf::from(home)
-                        .join(".config")
-                        .join(PKG_NAME)
-                        .join(format!("{}.toml", PKG_NAME));
-
-                    if Path::exists(&path) {
-                        return Opt::from_config_file(&path);
-                    }
-                }
             }
         }
 
         Ok(Opt::default())
     }
 
+    /// Writes the given `show` list and `theme` to the configuration file at `path`. Only these
+    /// two lines change, the comments and every other option of the file are kept as they are.
+    /// A `theme` of `None` leaves the theme line alone, `Some(None)` removes it.
+    pub fn save_preferences(
+        path: &Path,
+        show: &[data::ReadoutKey],
+        theme: Option<Option<&str>>,
+    ) -> Result<(), &'static str> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(_) => return Err("Failed to read configuration file."),
+        };
+
+        // Refuse to touch a file that couldn't be read back afterwards anyway.
+        toml::from_str::<toml::value::Table>(&contents)
+            .or(Err("Failed to parse configuration file."))?;
+
+        let show = toml::Value::Array(
+            show.iter()
+                .map(|key| toml::Value::String(key.to_string()))
+                .collect(),
+        );
+        let mut contents = set_top_level_key(&contents, "show", Some(&show));
+        if let Some(theme) = theme {
+            let theme = theme.map(|theme| toml::Value::String(theme.to_owned()));
+            contents = set_top_level_key(&contents, "theme", theme.as_ref());
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).or(Err("Failed to create configuration directory."))?;
+        }
+
+        std::fs::write(path, contents).or(Err("Failed to write configuration file."))
+    }
+
     /// Patches `opt` with the values provided in the command-line
     pub fn patch_args(&mut self, args: Self) {
         if args.version {
@@ -90,6 +139,10 @@ impl Opt {
             self.ascii_artists = true;
         }
 
+        if args.list_ascii {
+            self.list_ascii = true;
+        }
+
         if args.config.is_some() {
             self.config = args.config;
         }
@@ -98,6 +151,10 @@ impl Opt {
             self.theme = args.theme;
         }
 
+        if args.ascii.is_some() {
+            self.ascii = args.ascii;
+        }
+
         if args.show.is_some() {
             self.show = args.show;
         }
@@ -105,5 +162,113 @@ impl Opt {
         if args.interface.is_some() {
             self.interface = args.interface;
         }
+
+        if args.interactive {
+            self.interactive = true;
+        }
+
+        if args.color.is_some() {
+            self.color = args.color;
+        }
+
+        if args.color_depth.is_some() {
+            self.color_depth = args.color_depth;
+        }
+
+        if args.export.is_some() {
+            self.export = args.export;
+        }
+
+        if args.watch.is_some() {
+            self.watch = args.watch;
+        }
+    }
+}
+
+/// Sets `key`, which lives at the top of the TOML document `contents`, to `value` or removes it
+/// when `value` is `None`. Every other line is left as it is.
+fn set_top_level_key(contents: &str, key: &str, value: Option<&toml::Value>) -> String {
+    let lines: Vec<&str> = contents.lines().collect();
+
+    // Finds the lines that the top-level statements span, up to the first table. A statement
+    // ends on the first line that makes it parse on its own, e.g. the end of a multiline array.
+    let mut statements = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let line = lines[start].trim();
+        if line.starts_with('[') {
+            break;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            start += 1;
+            continue;
+        }
+
+        let end = (start..lines.len())
+            .find(|&end| {
+                toml::from_str::<toml::value::Table>(&lines[start..=end].join("\n")).is_ok()
+            })
+            .unwrap_or(lines.len() - 1);
+        let name = toml::from_str::<toml::value::Table>(&lines[start..=end].join("\n"))
+            .ok()
+            .and_then(|table| table.keys().next().cloned());
+
+        statements.push((start, end, name));
+        start = end + 1;
+    }
+
+    let line = value.map(|value| format!("{} = {}", key, value));
+    let mut lines: Vec<String> = lines.into_iter().map(String::from).collect();
+    match statements
+        .iter()
+        .find(|(_, _, name)| name.as_deref() == Some(key))
+    {
+        Some(&(start, end, _)) => {
+            lines.splice(start..=end, line);
+        }
+        None => {
+            let after_statements = statements.last().map_or(0, |&(_, end, _)| end + 1);
+            lines.splice(after_statements..after_statements, line);
+        }
+    }
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show() -> toml::Value {
+        toml::Value::Array(vec![toml::Value::String(String::from("Host"))])
+    }
+
+    #[test]
+    fn replaces_multiline_value_and_keeps_comments() {
+        let contents = "# My config\nshow = [\n  \"Kernel\",\n]\n\n# Spacing\nspacing = 1\n";
+        assert_eq!(
+            set_top_level_key(contents, "show", Some(&show())),
+            "# My config\nshow = [\"Host\"]\n\n# Spacing\nspacing = 1\n"
+        );
+    }
+
+    #[test]
+    fn inserts_missing_key_before_tables() {
+        let contents = "spacing = 1\n\n[bar]\nvisible = true\n";
+        assert_eq!(
+            set_top_level_key(contents, "show", Some(&show())),
+            "spacing = 1\nshow = [\"Host\"]\n\n[bar]\nvisible = true\n"
+        );
+    }
+
+    #[test]
+    fn removes_key_and_ignores_keys_of_tables() {
+        let contents = "theme = \"Helium\" # mine\n\n[box]\ntheme = \"kept\"\n";
+        assert_eq!(
+            set_top_level_key(contents, "theme", None),
+            "\n[box]\ntheme = \"kept\"\n"
+        );
     }
 }
@@ -1,11 +1,11 @@
 use crate::cli::Opt;
-use crate::theme::Theme;
+use crate::color;
+use crate::theme::{BarStyle, Theme};
 use clap::arg_enum;
 use libmacchina::traits::ShellFormat;
 use libmacchina::traits::{ReadoutError, ShellKind};
 use libmacchina::{BatteryReadout, GeneralReadout, KernelReadout, MemoryReadout, PackageReadout};
 use serde::{Deserialize, Serialize};
-use std::borrow::Cow;
 use tui::style::{Color, Style};
 use tui::text::{Span, Spans, Text};
 
@@ -35,75 +35,200 @@ arg_enum! {
     }
 }
 
+impl ReadoutKey {
+    /// Returns `true` for readouts whose values change while macchina is running, these are
+    /// re-collected on every tick of `--watch`.
+    pub fn is_volatile(&self) -> bool {
+        matches!(
+            self,
+            ReadoutKey::ProcessorLoad
+                | ReadoutKey::Memory
+                | ReadoutKey::Uptime
+                | ReadoutKey::Battery
+                | ReadoutKey::Backlight
+        )
+    }
+
+    /// Returns the name of the readout in themes, e.g. `cpu_load` in `[keys]` and
+    /// `[styles.cpu_load]`.
+    pub fn theme_name(&self) -> &'static str {
+        match self {
+            ReadoutKey::Host => "host",
+            ReadoutKey::Machine => "machine",
+            ReadoutKey::Kernel => "kernel",
+            ReadoutKey::Distribution => "distro",
+            ReadoutKey::OperatingSystem => "os",
+            ReadoutKey::DesktopEnvironment => "de",
+            ReadoutKey::WindowManager => "wm",
+            ReadoutKey::Packages => "packages",
+            ReadoutKey::Shell => "shell",
+            ReadoutKey::Terminal => "terminal",
+            ReadoutKey::LocalIP => "local_ip",
+            ReadoutKey::Backlight => "backlight",
+            ReadoutKey::Resolution => "resolution",
+            ReadoutKey::Uptime => "uptime",
+            ReadoutKey::Processor => "cpu",
+            ReadoutKey::ProcessorLoad => "cpu_load",
+            ReadoutKey::Memory => "memory",
+            ReadoutKey::Battery => "battery",
+        }
+    }
+
+    /// Returns the readout that themes call `name`, see `theme_name`.
+    pub fn from_theme_name(name: &str) -> Option<ReadoutKey> {
+        ReadoutKey::variants()
+            .iter()
+            .map(|variant| variant.parse::<ReadoutKey>().unwrap())
+            .find(|key| key.theme_name() == name)
+    }
+
+    /// Returns the key that is shown next to the readout unless the theme renames it.
+    pub fn default_label(&self) -> &'static str {
+        match self {
+            ReadoutKey::Host => "Host",
+            ReadoutKey::Machine => "Machine",
+            ReadoutKey::Kernel => "Kernel",
+            ReadoutKey::Distribution => "Distro",
+            ReadoutKey::OperatingSystem => "OS",
+            ReadoutKey::DesktopEnvironment => "DE",
+            ReadoutKey::WindowManager => "WM",
+            ReadoutKey::Packages => "Packages",
+            ReadoutKey::Shell => "Shell",
+            ReadoutKey::Terminal => "Terminal",
+            ReadoutKey::LocalIP => "Local IP",
+            ReadoutKey::Backlight => "Brightness",
+            ReadoutKey::Resolution => "Resolution",
+            ReadoutKey::Uptime => "Uptime",
+            ReadoutKey::Processor => "CPU",
+            ReadoutKey::ProcessorLoad => "CPU Load",
+            ReadoutKey::Memory => "Memory",
+            ReadoutKey::Battery => "Battery",
+        }
+    }
+}
+
+/// A readout and its value, along with the percentage it reports, if any, which the
+/// `[thresholds]` of the theme color its key by.
 #[derive(Debug, Clone)]
-pub struct Readout<'a>(pub ReadoutKey, pub Result<Text<'a>, ReadoutError>);
+pub struct Readout<'a>(
+    pub ReadoutKey,
+    pub Result<Text<'a>, ReadoutError>,
+    pub Option<f32>,
+);
 
 impl<'a> Readout<'a> {
     pub fn new_err(readout_key: ReadoutKey, err: ReadoutError) -> Readout<'a> {
-        Readout(readout_key, Err(err))
+        Readout(readout_key, Err(err), None)
     }
 
     pub fn new<T>(readout_key: ReadoutKey, text: T) -> Readout<'a>
     where
         T: Into<Text<'a>>,
     {
-        Readout(readout_key, Ok(text.into()))
+        Readout(readout_key, Ok(text.into()), None)
     }
-}
 
-fn colored_glyphs(glyph: &str, blocks: usize) -> String {
-    glyph
-        .repeat(blocks)
-        .chars()
-        .collect::<Vec<char>>()
-        .chunks(1)
-        .map(|c| c.iter().collect::<String>())
-        .collect::<Vec<String>>()
-        .join(" ")
+    pub fn with_percentage(mut self, percentage: f32) -> Readout<'a> {
+        self.2 = Some(percentage);
+        self
+    }
 }
 
-fn create_bar<'a>(theme: &Theme, blocks: usize) -> Spans<'a> {
-    if theme.get_bar_style().symbol_open == '\0' {
-        let mut span_vector = vec![Span::raw(""), Span::raw("")];
-
-        let glyph = theme.get_bar_style().glyph.clone();
-        let glyphs = colored_glyphs(&glyph, blocks);
+/// Returns the style `[thresholds]` give to `key` at `percentage`, plain below the levels.
+fn threshold_style(theme: &Theme, key: ReadoutKey, percentage: f32) -> Style {
+    match theme.get_threshold_color(key, percentage) {
+        Some(color) => Style::default().fg(color),
+        None => Style::default(),
+    }
+}
 
-        if blocks == 10 {
-            span_vector[0].content = Cow::from(glyphs);
-        } else {
-            span_vector[0].content = Cow::from(format!("{} ", glyphs));
+/// The block elements that fill one to seven eighths of a cell, from the left.
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Creates a bar showing `percentage`, whose filled segments are colored by the `[thresholds]`
+/// of `key`, the gradient of the bar, or the key color, in that order.
+fn create_bar<'a>(theme: &Theme, key: ReadoutKey, percentage: f32) -> Spans<'a> {
+    let bar = theme.get_bar_style();
+    let length = bar.length.max(1);
+
+    let threshold_color = theme.get_threshold_color(key, percentage);
+    let stops: Vec<(u8, u8, u8)> = bar
+        .gradient
+        .iter()
+        .map(|color| color::to_rgb(color.0, color::DEFAULT_FG))
+        .collect();
+    let segment_color = |i: usize| match threshold_color {
+        Some(color) => color,
+        None if !stops.is_empty() => {
+            color::gradient_color(&stops, i as f32 / (length - 1).max(1) as f32)
         }
-        span_vector[0].style = Style::default().fg(theme.get_key_color());
+        None => theme.get_key_color(),
+    };
+
+    let (filled, unfilled): (Vec<String>, Vec<String>) = match bar.style {
+        BarStyle::Eighths => {
+            let eighths = crate::bars::num_to_eighths(percentage, length);
+            let (full, partial) = (eighths / 8, eighths % 8);
+            let mut filled = vec![String::from("█"); full];
+            if partial > 0 {
+                filled.push(EIGHTHS[partial - 1].to_string());
+            }
 
-        span_vector[1].content = Cow::from(colored_glyphs(&glyph, 10 - blocks));
-        if theme.get_key_color() == Color::White {
-            span_vector[1].content = Cow::from(span_vector[1].content.replace(&glyph, " "));
+            let unfilled = vec![String::from(" "); length - filled.len()];
+            (filled, unfilled)
+        }
+        BarStyle::Spaced | BarStyle::Continuous => {
+            let blocks = crate::bars::num_to_blocks(percentage, length);
+            (
+                vec![bar.glyph.clone(); blocks],
+                vec![bar.glyph.clone(); length - blocks],
+            )
         }
-        return Spans::from(span_vector);
+    };
+
+    let separator = match bar.style {
+        BarStyle::Spaced => " ",
+        _ => "",
+    };
+
+    let delimited = bar.symbol_open != '\0';
+    let mut span_vector = Vec::new();
+    if delimited {
+        span_vector.push(Span::raw(format!("{} ", bar.symbol_open)));
     }
 
-    let mut span_vector = vec![
-        Span::raw(format!("{} ", theme.get_bar_style().symbol_open)),
-        Span::raw(""),
-        Span::raw(""),
-        Span::raw(format!(" {}", theme.get_bar_style().symbol_close)),
-    ];
+    let any_filled = !filled.is_empty();
+    for (i, segment) in filled.into_iter().enumerate() {
+        if i > 0 {
+            span_vector.push(Span::raw(separator));
+        }
+        span_vector.push(Span::styled(segment, Style::default().fg(segment_color(i))));
+    }
 
-    let glyph = theme.get_bar_style().glyph.clone();
-    let glyphs = colored_glyphs(&glyph, blocks);
+    if !unfilled.is_empty() {
+        let (unfilled, unfilled_style) = match &bar.unfilled_color {
+            Some(color) => (unfilled, Style::default().fg(color.0)),
+            // In the default color, they couldn't be told apart from white filled segments.
+            None if theme.get_key_color() == Color::White => {
+                (vec![String::from(" "); unfilled.len()], Style::default())
+            }
+            None => (unfilled, Style::default()),
+        };
+        let unfilled = unfilled.join(separator);
+        if any_filled {
+            span_vector.push(Span::raw(separator));
+        }
+        span_vector.push(Span::styled(unfilled, unfilled_style));
+    }
 
-    if blocks == 10 {
-        span_vector[1].content = Cow::from(glyphs);
-    } else {
-        span_vector[1].content = Cow::from(format!("{} ", glyphs));
+    if delimited {
+        span_vector.push(Span::raw(format!(" {}", bar.symbol_close)));
     }
-    span_vector[1].style = Style::default().fg(theme.get_key_color());
 
-    span_vector[2].content = Cow::from(colored_glyphs(&glyph, 10 - blocks));
-    if theme.get_key_color() == Color::White {
-        span_vector[2].content = Cow::from(span_vector[2].content.replace(&glyph, " "));
+    if bar.show_percentage {
+        span_vector.push(Span::raw(format!(" {:.0}%", percentage)));
     }
+
     Spans::from(span_vector)
 }
 
@@ -284,34 +409,46 @@ pub fn get_all_readouts<'a>(
 
     if should_display.contains(&ReadoutKey::Backlight) {
         match (general_readout.backlight(), theme.is_using_bars()) {
-            (Ok(b), false) => {
-                readout_values.push(Readout::new(ReadoutKey::Backlight, format!("{}%", b)))
-            }
-            (Ok(b), true) => readout_values.push(Readout::new(
-                ReadoutKey::Backlight,
-                create_bar(theme, crate::bars::num_to_blocks(b as u8)),
-            )),
+            (Ok(b), false) => readout_values.push(
+                Readout::new(
+                    ReadoutKey::Backlight,
+                    Text::styled(
+                        format!("{}%", b),
+                        threshold_style(theme, ReadoutKey::Backlight, b as f32),
+                    ),
+                )
+                .with_percentage(b as f32),
+            ),
+            (Ok(b), true) => readout_values.push(
+                Readout::new(
+                    ReadoutKey::Backlight,
+                    create_bar(theme, ReadoutKey::Backlight, b as f32),
+                )
+                .with_percentage(b as f32),
+            ),
             (Err(e), _) => readout_values.push(Readout::new_err(ReadoutKey::Backlight, e)),
         }
     }
 
     if should_display.contains(&ReadoutKey::ProcessorLoad) {
         match (general_readout.cpu_usage(), theme.is_using_bars()) {
-            (Ok(u), true) => {
-                if u > 100 {
-                    readout_values.push(Readout::new(
-                        ReadoutKey::ProcessorLoad,
-                        create_bar(theme, crate::bars::num_to_blocks(100_u8)),
-                    ))
-                }
-                readout_values.push(Readout::new(
+            (Ok(u), true) => readout_values.push(
+                Readout::new(
                     ReadoutKey::ProcessorLoad,
-                    create_bar(theme, crate::bars::num_to_blocks(u as u8)),
-                ))
-            }
-            (Ok(u), _) => {
-                readout_values.push(Readout::new(ReadoutKey::ProcessorLoad, format_cpu_usage(u)))
-            }
+                    create_bar(theme, ReadoutKey::ProcessorLoad, (u as f32).min(100.0)),
+                )
+                .with_percentage(u as f32),
+            ),
+            (Ok(u), _) => readout_values.push(
+                Readout::new(
+                    ReadoutKey::ProcessorLoad,
+                    Text::styled(
+                        format_cpu_usage(u),
+                        threshold_style(theme, ReadoutKey::ProcessorLoad, u as f32),
+                    ),
+                )
+                .with_percentage(u as f32),
+            ),
             (Err(e), _) => readout_values.push(Readout::new_err(ReadoutKey::ProcessorLoad, e)),
         }
     }
@@ -326,11 +463,20 @@ pub fn get_all_readouts<'a>(
 
         match (total, used) {
             (Ok(total), Ok(used)) => {
+                let percentage = used as f32 / total as f32 * 100.0;
                 if theme.is_using_bars() {
-                    let bar = create_bar(theme, crate::bars::memory(used, total));
-                    readout_values.push(Readout::new(ReadoutKey::Memory, bar))
+                    let bar = create_bar(theme, ReadoutKey::Memory, percentage);
+                    readout_values
+                        .push(Readout::new(ReadoutKey::Memory, bar).with_percentage(percentage))
                 } else {
-                    readout_values.push(Readout::new(ReadoutKey::Memory, format_mem(total, used)))
+                    let style = threshold_style(theme, ReadoutKey::Memory, percentage);
+                    readout_values.push(
+                        Readout::new(
+                            ReadoutKey::Memory,
+                            Text::styled(format_mem(total, used), style),
+                        )
+                        .with_percentage(percentage),
+                    )
                 }
             }
             (Err(e), _) | (_, Err(e)) => {
@@ -352,10 +498,12 @@ pub fn get_all_readouts<'a>(
         match (percentage, state) {
             (Ok(p), Ok(s)) => {
                 if theme.is_using_bars() {
-                    let bar = create_bar(theme, crate::bars::num_to_blocks(p));
-                    readout_values.push(Readout::new(key, bar));
+                    let bar = create_bar(theme, key, p as f32);
+                    readout_values.push(Readout::new(key, bar).with_percentage(p as f32));
                 } else {
-                    readout_values.push(Readout::new(key, format_bat(p, s)));
+                    let style = threshold_style(theme, key, p as f32);
+                    let value = Text::styled(format_bat(p, s), style);
+                    readout_values.push(Readout::new(key, value).with_percentage(p as f32));
                 }
             }
             (Err(e), _) | (_, Err(e)) => readout_values.push(Readout::new_err(key, e)),
@@ -364,3 +512,25 @@ pub fn get_all_readouts<'a>(
 
     readout_values
 }
+
+/// Re-collects the volatile readouts in `readouts`, leaving every other readout untouched.
+pub fn refresh_readouts(opt: &Opt, theme: &Theme, readouts: &mut Vec<Readout>) {
+    let volatile: Vec<ReadoutKey> = readouts
+        .iter()
+        .map(|r| r.0)
+        .filter(|k| k.is_volatile())
+        .collect();
+
+    if volatile.is_empty() {
+        return;
+    }
+
+    for fresh in get_all_readouts(opt, theme, volatile)
+        .into_iter()
+        .filter(|r| r.0.is_volatile())
+    {
+        if let Some(old) = readouts.iter_mut().find(|r| r.0 == fresh.0) {
+            *old = fresh;
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use super::{escape, rows, to_hex};
+use crate::color::DEFAULT_FG;
+use std::io::{self, Write};
+use tui::buffer::Buffer;
+use tui::style::{Color, Modifier};
+
+/// Writes the buffer as a standalone HTML document, with the fetch inside of a `<pre>` element
+/// and every run of styled cells wrapped in a `<span>`.
+pub fn write<W: Write>(out: &mut W, buf: &Buffer, background: (u8, u8, u8)) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, r#"<meta charset="utf-8">"#)?;
+    writeln!(out, "<title>macchina</title>")?;
+    writeln!(out, "</head>")?;
+    writeln!(
+        out,
+        r#"<body style="margin: 0; background-color: {};">"#,
+        to_hex(background)
+    )?;
+    write!(
+        out,
+        r#"<pre style="margin: 0; padding: 1em; font-family: monospace; color: {}; background-color: {};">"#,
+        to_hex(DEFAULT_FG),
+        to_hex(background)
+    )?;
+
+    for runs in rows(buf) {
+        for run in runs {
+            let text = escape(&run.text);
+            if run.fg == Color::Reset && run.bg == Color::Reset && run.modifier.is_empty() {
+                write!(out, "{}", text)?;
+                continue;
+            }
+
+            let (fg, bg) = run.colors(background);
+            let mut style = format!("color: {};", to_hex(fg));
+            if run.bg != Color::Reset || run.modifier.contains(Modifier::REVERSED) {
+                style.push_str(&format!(" background-color: {};", to_hex(bg)));
+            }
+            if run.modifier.contains(Modifier::BOLD) {
+                style.push_str(" font-weight: bold;");
+            }
+            if run.modifier.contains(Modifier::ITALIC) {
+                style.push_str(" font-style: italic;");
+            }
+            if run.modifier.contains(Modifier::UNDERLINED) {
+                style.push_str(" text-decoration: underline;");
+            }
+
+            write!(out, r#"<span style="{}">{}</span>"#, style, text)?;
+        }
+
+        writeln!(out)?;
+    }
+
+    writeln!(out, "</pre>")?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::layout::Rect;
+    use tui::style::Style;
+
+    fn render(buf: &Buffer) -> String {
+        let mut out = Vec::new();
+        write(&mut out, buf, (0, 0, 0)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn escapes_text_and_colors_runs() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 2));
+        buf.set_string(0, 0, "a<b", Style::default().fg(Color::Red));
+        buf.set_string(3, 0, " & c", Style::default());
+        buf.set_string(
+            0,
+            1,
+            "x",
+            Style::default()
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+        );
+        let html = render(&buf);
+
+        assert!(
+            html.contains(r#"<span style="color: #cd0000;">a&lt;b</span> &amp; c"#),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains(
+                r#"<span style="color: #e5e5e5; background-color: #0000ee; font-weight: bold;">x</span>"#
+            ),
+            "{}",
+            html
+        );
+    }
+}
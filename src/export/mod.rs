@@ -0,0 +1,103 @@
+mod html;
+mod png;
+mod svg;
+
+use crate::cli::ExportFormat;
+use crate::color::{self, DEFAULT_BG, DEFAULT_FG};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use tui::buffer::{Buffer, Cell};
+use tui::style::{Color, Modifier};
+use unicode_width::UnicodeWidthStr;
+
+pub fn to_hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// A horizontal run of cells sharing the same style.
+pub struct Run {
+    /// The column of the first cell of the run.
+    pub x: u16,
+    /// The number of columns covered by the run.
+    pub width: u16,
+    pub text: String,
+    pub fg: Color,
+    pub bg: Color,
+    pub modifier: Modifier,
+}
+
+impl Run {
+    /// Returns the resolved foreground and background colors, taking `Modifier::REVERSED` into
+    /// account.
+    pub fn colors(&self, background: (u8, u8, u8)) -> ((u8, u8, u8), (u8, u8, u8)) {
+        let fg = color::to_rgb(self.fg, DEFAULT_FG);
+        let bg = color::to_rgb(self.bg, background);
+
+        if self.modifier.contains(Modifier::REVERSED) {
+            (bg, fg)
+        } else {
+            (fg, bg)
+        }
+    }
+
+    fn has_style_of(&self, cell: &Cell) -> bool {
+        self.fg == cell.fg && self.bg == cell.bg && self.modifier == cell.modifier
+    }
+}
+
+/// Splits every row of the buffer that has content into runs of equally styled cells, trailing
+/// empty cells and rows are left out.
+pub fn rows(buf: &Buffer) -> Vec<Vec<Run>> {
+    crate::plain::visible_rows(buf)
+        .into_iter()
+        .map(|cells| {
+            let mut runs: Vec<Run> = Vec::new();
+            for (x, cell) in cells {
+                let width = cell.symbol.width().max(1) as u16;
+                match runs.last_mut() {
+                    Some(run) if run.has_style_of(cell) => {
+                        run.text.push_str(&cell.symbol);
+                        run.width += width;
+                    }
+                    _ => runs.push(Run {
+                        x,
+                        width,
+                        text: cell.symbol.clone(),
+                        fg: cell.fg,
+                        bg: cell.bg,
+                        modifier: cell.modifier,
+                    }),
+                }
+            }
+            runs
+        })
+        .collect()
+}
+
+/// Escapes the characters that have a special meaning in XML and HTML.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes the content of `buf` to `path` in the given format, on top of `background`.
+pub(crate) fn export(
+    buf: &Buffer,
+    format: ExportFormat,
+    path: &Path,
+    background: Color,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let background = color::to_rgb(background, DEFAULT_BG);
+
+    match format {
+        ExportFormat::Svg => svg::write(&mut out, buf, background)?,
+        ExportFormat::Html => html::write(&mut out, buf, background)?,
+        ExportFormat::Png => png::write(&mut out, buf, background)?,
+    }
+
+    out.flush()
+}
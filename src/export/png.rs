@@ -0,0 +1,208 @@
+use super::rows;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use tui::buffer::Buffer;
+use tui::style::{Color, Modifier};
+use unicode_width::UnicodeWidthChar;
+
+lazy_static! {
+    static ref FONT: Font = Font::parse(include_bytes!("../../fonts/macchina-16x32.psf"))
+        .expect("The bundled font is not a valid PSF2 font.");
+}
+
+/// A PC Screen Font (version 2) bitmap font with a unicode table, see
+/// https://www.win.tue.nl/~aeb/linux/kbd/font-formats-1.html
+struct Font {
+    width: usize,
+    height: usize,
+    bytes_per_row: usize,
+    bitmaps: &'static [u8],
+    glyphs: HashMap<char, usize>,
+}
+
+impl Font {
+    fn parse(data: &'static [u8]) -> Option<Font> {
+        let field = |i: usize| -> Option<usize> {
+            let bytes = data.get(i * 4..i * 4 + 4)?;
+            Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        };
+
+        if data.get(0..4)? != [0x72, 0xb5, 0x4a, 0x86] {
+            return None;
+        }
+
+        let (header_size, flags, length, glyph_size) = (field(2)?, field(3)?, field(4)?, field(5)?);
+        let (height, width) = (field(6)?, field(7)?);
+        let table_start = header_size + length * glyph_size;
+        let bitmaps = data.get(header_size..table_start)?;
+
+        // Without a unicode table, glyphs are indexed by their code point.
+        let mut glyphs = HashMap::new();
+        if flags & 1 == 0 {
+            for i in 0..length {
+                glyphs.insert(char::from_u32(i as u32)?, i);
+            }
+        } else {
+            let table = data.get(table_start..)?;
+            for (i, entry) in table.split(|b| *b == 0xff).take(length).enumerate() {
+                // Sequences of combining characters follow a 0xfe byte, they aren't needed here.
+                let single = entry.split(|b| *b == 0xfe).next().unwrap_or_default();
+                for c in String::from_utf8_lossy(single).chars() {
+                    glyphs.insert(c, i);
+                }
+            }
+        }
+
+        Some(Font {
+            width,
+            height,
+            bytes_per_row: width.div_ceil(8),
+            bitmaps,
+            glyphs,
+        })
+    }
+
+    /// Returns the bitmap of `c`, falling back to a question mark for missing glyphs.
+    fn glyph(&self, c: char) -> Option<&[u8]> {
+        let index = self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?'))?;
+        let size = self.bytes_per_row * self.height;
+        self.bitmaps.get(index * size..(index + 1) * size)
+    }
+
+    fn is_set(&self, glyph: &[u8], x: usize, y: usize) -> bool {
+        glyph[y * self.bytes_per_row + x / 8] & (0x80 >> (x % 8)) != 0
+    }
+}
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize, background: (u8, u8, u8)) -> Canvas {
+        let (r, g, b) = background;
+        Canvas {
+            width,
+            height,
+            pixels: [r, g, b].repeat(width * height),
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize, (r, g, b): (u8, u8, u8)) {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 3;
+            self.pixels[i..i + 3].copy_from_slice(&[r, g, b]);
+        }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: (u8, u8, u8)) {
+        for py in y..y + height {
+            for px in x..x + width {
+                self.set(px, py, color);
+            }
+        }
+    }
+}
+
+/// Rasterizes the buffer with the bundled bitmap font and writes it as a PNG image, every cell
+/// takes up as many pixels as a glyph of the font.
+pub fn write<W: Write>(out: &mut W, buf: &Buffer, background: (u8, u8, u8)) -> io::Result<()> {
+    let font = &*FONT;
+    let rows = rows(buf);
+    let columns = rows
+        .iter()
+        .filter_map(|runs| runs.last())
+        .map(|run| (run.x + run.width) as usize)
+        .max()
+        .unwrap_or(0);
+
+    let mut canvas = Canvas::new(
+        (columns * font.width).max(1),
+        (rows.len() * font.height).max(1),
+        background,
+    );
+
+    for (y, runs) in rows.iter().enumerate() {
+        let top = y * font.height;
+
+        for run in runs {
+            let (fg, bg) = run.colors(background);
+            let left = run.x as usize * font.width;
+
+            if run.bg != Color::Reset || run.modifier.contains(Modifier::REVERSED) {
+                canvas.fill(left, top, run.width as usize * font.width, font.height, bg);
+            }
+
+            let mut x = left;
+            for c in run.text.chars() {
+                let width = c.width().unwrap_or(0);
+                if width == 0 {
+                    continue;
+                }
+
+                if let Some(glyph) = font.glyph(c) {
+                    for gy in 0..font.height {
+                        for gx in 0..font.width {
+                            if font.is_set(glyph, gx, gy) {
+                                canvas.set(x + gx, top + gy, fg);
+                                // Thicken the strokes to fake a bold typeface.
+                                if run.modifier.contains(Modifier::BOLD) {
+                                    canvas.set(x + gx + 1, top + gy, fg);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if run.modifier.contains(Modifier::UNDERLINED) {
+                    canvas.fill(x, top + font.height - 3, width * font.width, 2, fg);
+                }
+
+                x += width * font.width;
+            }
+        }
+    }
+
+    let mut encoder = png::Encoder::new(out, canvas.width as u32, canvas.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let to_io_error = |e: png::EncodingError| io::Error::other(e);
+    let mut writer = encoder.write_header().map_err(to_io_error)?;
+    writer.write_image_data(&canvas.pixels).map_err(to_io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::layout::Rect;
+    use tui::style::Style;
+
+    #[test]
+    fn sizes_the_image_by_the_glyphs_of_the_font() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 2));
+        buf.set_string(0, 0, "abc", Style::default());
+        buf.set_string(0, 1, "x", Style::default().bg(Color::Blue));
+
+        let mut out = Vec::new();
+        write(&mut out, &buf, (0, 0, 0)).unwrap();
+
+        let mut reader = png::Decoder::new(&out[..]).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+
+        assert_eq!((FONT.width, FONT.height), (16, 32));
+        assert_eq!((info.width, info.height), (3 * 16, 2 * 32));
+
+        // The background of the second row covers the whole cell.
+        let pixel = |x: usize, y: usize| {
+            let i = (y * info.width as usize + x) * 3;
+            (pixels[i], pixels[i + 1], pixels[i + 2])
+        };
+        assert_eq!(pixel(0, 32), (0, 0, 238));
+        assert_eq!(pixel(15, 63), (0, 0, 238));
+        assert_eq!(pixel(16, 32), (0, 0, 0));
+    }
+}
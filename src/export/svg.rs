@@ -0,0 +1,123 @@
+use super::{escape, rows, to_hex};
+use std::io::{self, Write};
+use tui::buffer::Buffer;
+use tui::style::{Color, Modifier};
+
+const CELL_WIDTH: u32 = 9;
+const CELL_HEIGHT: u32 = 18;
+const FONT_SIZE: u32 = 15;
+
+/// Writes the buffer as an SVG image, every cell is `CELL_WIDTH` by `CELL_HEIGHT` pixels and
+/// runs of text are stretched to fit their cells exactly.
+pub fn write<W: Write>(out: &mut W, buf: &Buffer, background: (u8, u8, u8)) -> io::Result<()> {
+    let rows = rows(buf);
+    let columns = rows
+        .iter()
+        .filter_map(|runs| runs.last())
+        .map(|run| (run.x + run.width) as u32)
+        .max()
+        .unwrap_or(0);
+
+    let width = columns * CELL_WIDTH;
+    let height = rows.len() as u32 * CELL_HEIGHT;
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )?;
+    writeln!(
+        out,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        to_hex(background)
+    )?;
+    writeln!(
+        out,
+        r#"<g font-family="monospace" font-size="{}" xml:space="preserve">"#,
+        FONT_SIZE
+    )?;
+
+    for (y, runs) in rows.iter().enumerate() {
+        let top = y as u32 * CELL_HEIGHT;
+
+        for run in runs {
+            let (fg, bg) = run.colors(background);
+            let left = run.x as u32 * CELL_WIDTH;
+            let run_width = run.width as u32 * CELL_WIDTH;
+
+            if run.bg != Color::Reset || run.modifier.contains(Modifier::REVERSED) {
+                writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    left,
+                    top,
+                    run_width,
+                    CELL_HEIGHT,
+                    to_hex(bg)
+                )?;
+            }
+
+            if run.text.trim().is_empty() {
+                continue;
+            }
+
+            let mut attributes = format!(r#"fill="{}""#, to_hex(fg));
+            if run.modifier.contains(Modifier::BOLD) {
+                attributes.push_str(r#" font-weight="bold""#);
+            }
+            if run.modifier.contains(Modifier::ITALIC) {
+                attributes.push_str(r#" font-style="italic""#);
+            }
+            if run.modifier.contains(Modifier::UNDERLINED) {
+                attributes.push_str(r#" text-decoration="underline""#);
+            }
+
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" textLength="{}" lengthAdjust="spacingAndGlyphs" {}>{}</text>"#,
+                left,
+                top + CELL_HEIGHT * 3 / 4,
+                run_width,
+                attributes,
+                escape(&run.text)
+            )?;
+        }
+    }
+
+    writeln!(out, "</g>")?;
+    writeln!(out, "</svg>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::layout::Rect;
+    use tui::style::Style;
+
+    fn render(buf: &Buffer) -> String {
+        let mut out = Vec::new();
+        write(&mut out, buf, (0, 0, 0)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn escapes_text_and_colors_runs() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 2));
+        buf.set_string(0, 0, "a<b & c", Style::default().fg(Color::Red));
+        buf.set_string(0, 1, "x", Style::default().bg(Color::Blue));
+        let svg = render(&buf);
+
+        assert!(svg.contains(r#"width="63" height="36""#), "{}", svg);
+        assert!(
+            svg.contains(r##"fill="#cd0000">a&lt;b &amp; c</text>"##),
+            "{}",
+            svg
+        );
+        assert!(
+            svg.contains(r##"<rect x="0" y="18" width="9" height="18" fill="#0000ee"/>"##),
+            "{}",
+            svg
+        );
+    }
+}
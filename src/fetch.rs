@@ -0,0 +1,568 @@
+use crate::animation::Animation;
+use crate::ascii;
+use crate::cli::{ColorDepth, MacchinaColor, Opt};
+use crate::data::{Readout, ReadoutKey};
+use crate::extra;
+use crate::paths;
+use crate::picture::{self, Graphic};
+use crate::theme::{Alignment, BoxTarget, Position, Theme};
+use crate::widgets::readout::{self, ReadoutList};
+use rand::Rng;
+use std::io;
+use std::str::FromStr;
+use tui::buffer::Buffer;
+use tui::layout::{Margin, Rect};
+use tui::style::Style;
+use tui::text::{Span, Text};
+use tui::widgets::{Block, Paragraph, Widget};
+
+fn draw_ascii(ascii: Text<'static>, x: u16, y: u16, tmp_buffer: &mut Buffer) -> Rect {
+    let ascii_rect = Rect {
+        x,
+        y,
+        width: ascii.width() as u16,
+        height: ascii.height() as u16,
+    };
+
+    // The art is truncated if the terminal isn't wide enough to fit it.
+    if ascii_rect.intersects(tmp_buffer.area) {
+        Paragraph::new(ascii).render(ascii_rect.intersection(tmp_buffer.area), tmp_buffer);
+    }
+
+    ascii_rect
+}
+
+/// Returns the offset that `alignment` gives to something `size` long within `space`.
+fn align(alignment: Alignment, space: u16, size: u16) -> u16 {
+    let free = space.saturating_sub(size);
+    match alignment {
+        Alignment::Start => 0,
+        Alignment::Center => free / 2,
+        Alignment::End => free,
+    }
+}
+
+/// Returns the width taken up by art `ascii_width` wide and readouts `list_width` wide, placed
+/// at `position`. They are one cell away from the edges and two columns away from each other,
+/// and without art the readouts are two columns away from the left edge.
+fn fetch_width(position: Position, ascii_width: u16, list_width: u16) -> u16 {
+    match position {
+        _ if ascii_width == 0 => 2 + list_width + 1,
+        Position::Left | Position::Right => 1 + ascii_width + 2 + list_width + 1,
+        Position::Top | Position::Bottom => 1 + ascii_width.max(list_width) + 1,
+    }
+}
+
+/// Returns the box described by the theme, without its inner margin.
+fn create_box(theme: &Theme) -> Block<'static> {
+    let mut block = Block::default()
+        .border_type(theme.get_box_border_type())
+        .borders(theme.get_box_borders())
+        .title_alignment(theme.get_box_title_alignment());
+
+    // An empty title would still take up a row when the top side isn't drawn.
+    let title = theme.get_box_title();
+    if !title.is_empty() {
+        let style = theme
+            .get_box_title_color()
+            .map_or_else(Style::default, |color| Style::default().fg(color));
+        block = block.title(Span::styled(title, style));
+    }
+
+    if let Some(color) = theme.get_box_border_color() {
+        block = block.border_style(Style::default().fg(color));
+    }
+
+    block
+}
+
+fn box_margin(theme: &Theme) -> Margin {
+    Margin {
+        horizontal: theme.get_horizontal_margin(),
+        vertical: theme.get_vertical_margin(),
+    }
+}
+
+fn create_readout_list<'a>(data: Vec<Readout<'a>>, theme: &'a Theme) -> ReadoutList<'a> {
+    let mut list = ReadoutList::new(data, theme);
+
+    if theme.is_box_visible() && theme.get_box_target() == BoxTarget::Readouts {
+        list = list
+            .block_inner_margin(box_margin(theme))
+            .block(create_box(theme));
+    }
+
+    list
+}
+
+/// Returns the ASCII art that should be drawn next to `readout_count` readouts, if any. The art
+/// of the running distribution, and the big art of the platform, are only picked if `fits`
+/// accepts their width.
+fn select_fetch_ascii(
+    theme: &Theme,
+    readout_count: usize,
+    fits: &dyn Fn(u16) -> bool,
+) -> io::Result<Option<Text<'static>>> {
+    if theme.is_ascii_hidden() {
+        return Ok(None);
+    }
+
+    if let Some(file_path) = theme.get_custom_ascii_path() {
+        let file_path = extra::expand_home(file_path).expect("Failed to expand ~ to HOME");
+        let ascii_art = if picture::is_image(&file_path) {
+            picture::get_ascii_from_image(
+                &file_path,
+                theme.get_custom_ascii_image(),
+                theme
+                    .using_custom_ascii_color()
+                    .then(|| theme.get_custom_ascii_color()),
+            )?
+        } else {
+            // Animated art is shown as its first frame wherever it can't be played.
+            ascii::get_ascii_frames(
+                &file_path,
+                theme.get_custom_ascii_animation().separator.as_deref(),
+                theme
+                    .using_custom_ascii_color()
+                    .then(|| theme.get_custom_ascii_color()),
+            )?
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+        };
+
+        // If the file is empty just default to disabled
+        if ascii_art.width() == 0 {
+            return Ok(None);
+        }
+
+        return Ok(Some(ascii_art));
+    }
+
+    if let Some(id) = theme.get_builtin_ascii() {
+        return match ascii::get_builtin_ascii(id) {
+            Some(ascii_art) => Ok(Some(ascii_art)),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "There is no built-in ASCII art called \"{}\", see --list-ascii",
+                    id
+                ),
+            )),
+        };
+    }
+
+    // The distribution specific art only comes in one size, so it's shown whenever it fits.
+    let distro = ascii::detect_builtin_ascii()
+        .and_then(ascii::get_builtin_ascii)
+        .map(ascii::crop);
+    if let Some(ascii_art) = distro.filter(|a| fits(a.width() as u16)) {
+        return Ok(Some(ascii_art));
+    }
+
+    // prefer smaller ascii if condition is satisfied
+    let small = readout_count <= 6 || theme.prefers_small_ascii();
+
+    // The small art of the platform is the fallback for the big one.
+    if !small {
+        let big = select_ascii(false).map(ascii::crop);
+        if let Some(ascii_art) = big.filter(|a| fits(a.width() as u16)) {
+            return Ok(Some(ascii_art));
+        }
+    }
+
+    Ok(select_ascii(true))
+}
+
+/// Returns the image that the terminal should draw in place of the ASCII art, if the custom
+/// ASCII art is an image and a graphics protocol is available.
+pub(crate) fn select_fetch_graphic(theme: &Theme) -> io::Result<Option<Graphic>> {
+    if theme.is_ascii_hidden() {
+        return Ok(None);
+    }
+
+    match theme.get_custom_ascii_path() {
+        Some(file_path) => {
+            let file_path = extra::expand_home(file_path).expect("Failed to expand ~ to HOME");
+            if !picture::is_image(&file_path) {
+                return Ok(None);
+            }
+
+            Graphic::new(&file_path, theme.get_custom_ascii_image())
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns the custom ASCII art as an animation, if it is made of several frames.
+pub(crate) fn select_fetch_animation(
+    theme: &Theme,
+    color_depth: ColorDepth,
+) -> io::Result<Option<Animation>> {
+    if theme.is_ascii_hidden() {
+        return Ok(None);
+    }
+
+    let file_path = match theme.get_custom_ascii_path() {
+        Some(file_path) => extra::expand_home(file_path).expect("Failed to expand ~ to HOME"),
+        None => return Ok(None),
+    };
+
+    if picture::is_image(&file_path) {
+        return Ok(None);
+    }
+
+    let options = theme.get_custom_ascii_animation();
+    let mut frames = ascii::get_ascii_frames(
+        &file_path,
+        options.separator.as_deref(),
+        theme
+            .using_custom_ascii_color()
+            .then(|| theme.get_custom_ascii_color()),
+    )?;
+
+    if frames.len() < 2 {
+        return Ok(None);
+    }
+
+    for frame in frames.iter_mut() {
+        ascii::apply_theme_colors(frame, theme);
+    }
+
+    Animation::new(frames, options, color_depth).map(Some)
+}
+
+/// Draws the ASCII art and the readouts into a buffer that is just large enough to hold them,
+/// following the theme's layout. Its width is capped to `max_width` (usually the width of the
+/// terminal), and art that should go beside the readouts is put above them if they don't fit.
+/// If `reserved_size` is given, an area of that many columns and rows is left blank in place of
+/// the ASCII art, for a `Graphic` or an `Animation` to be drawn over.
+/// The area of the ASCII art is returned along with the buffer.
+pub(crate) fn draw_fetch(
+    readout_data: Vec<Readout>,
+    theme: &Theme,
+    max_width: Option<u16>,
+    reserved_size: Option<(u16, u16)>,
+) -> io::Result<(Buffer, Rect)> {
+    let readout_count = readout_data.len();
+    let mut list = create_readout_list(readout_data, theme);
+    let (list_width, _) = list.size();
+
+    let alignment = theme.get_layout_alignment();
+    let mut position = theme.get_layout_position();
+
+    // The room taken up by a box around the art, or around everything.
+    let the_box = theme.is_box_visible().then(|| create_box(theme));
+    let insets = the_box
+        .as_ref()
+        .map(|block| readout::block_insets(block, &box_margin(theme)))
+        .unwrap_or_default();
+    let (ascii_insets, outer_insets) = match theme.get_box_target() {
+        BoxTarget::Ascii => (insets, Rect::default()),
+        BoxTarget::Both => (Rect::default(), insets),
+        BoxTarget::Readouts => (Rect::default(), Rect::default()),
+    };
+    let max_width = max_width.map(|max_width| max_width.saturating_sub(outer_insets.width));
+
+    // Above or below the readouts, the art only has to fit on its own.
+    let fits = |ascii_width: u16| match (max_width, position) {
+        (None, _) => true,
+        (Some(max_width), Position::Left | Position::Right) => {
+            fetch_width(position, ascii_width + ascii_insets.width, list_width) <= max_width
+        }
+        (Some(max_width), _) => {
+            fetch_width(position, ascii_width + ascii_insets.width, 0) <= max_width
+        }
+    };
+
+    let ascii = match reserved_size {
+        Some(_) => None,
+        None => select_fetch_ascii(theme, readout_count, &fits)?.map(|ascii| {
+            let mut ascii = ascii::crop(ascii);
+
+            // Art that is wider than the terminal would get cut off even above the readouts,
+            // but colored pixel art survives being shrunk.
+            if let Some(max_width) = max_width {
+                let available = max_width.saturating_sub(2 + ascii_insets.width);
+                if ascii.width() as u16 > available && ascii::is_truecolor(&ascii) {
+                    ascii = ascii::downscale(&ascii, available);
+                }
+            }
+
+            ascii::apply_theme_colors(&mut ascii, theme);
+            ascii
+        }),
+    };
+    let (art_width, art_height) = ascii
+        .as_ref()
+        .map(|a| (a.width() as u16, a.height() as u16))
+        .or(reserved_size)
+        .unwrap_or((0, 0));
+    let ascii_insets = if art_width > 0 {
+        ascii_insets
+    } else {
+        Rect::default()
+    };
+    let (ascii_width, ascii_height) = (
+        art_width + ascii_insets.width,
+        art_height + ascii_insets.height,
+    );
+
+    let too_wide = |position: Position, list_width: u16| {
+        max_width
+            .is_some_and(|max_width| fetch_width(position, ascii_width, list_width) > max_width)
+    };
+    if matches!(position, Position::Left | Position::Right) && too_wide(position, list_width) {
+        position = Position::Top;
+    }
+
+    // The readouts are only flowed into more columns if these still fit next to the art.
+    let mut columns = theme.get_max_columns();
+    loop {
+        list = list.columns(columns);
+        if columns == 1 || !too_wide(position, list.size().0) {
+            break;
+        }
+        columns -= 1;
+    }
+
+    let (list_width, list_height) = list.size();
+    let mut width = fetch_width(position, ascii_width, list_width);
+
+    // The art and the readouts are one row below the top edge, and one row away from each other
+    // when one is above the other.
+    let tallest = ascii_height.max(list_height);
+    let widest = ascii_width.max(list_width);
+    let ((ascii_x, ascii_y), (list_x, list_y), height) = match position {
+        _ if ascii_width == 0 => ((0, 1), (2, 1), 1 + list_height),
+        Position::Left => (
+            (1, 1 + align(alignment, tallest, ascii_height)),
+            (
+                1 + ascii_width + 2,
+                1 + align(alignment, tallest, list_height),
+            ),
+            1 + tallest,
+        ),
+        Position::Right => (
+            (
+                1 + list_width + 2,
+                1 + align(alignment, tallest, ascii_height),
+            ),
+            (1, 1 + align(alignment, tallest, list_height)),
+            1 + tallest,
+        ),
+        Position::Top => (
+            (1 + align(alignment, widest, ascii_width), 1),
+            (
+                1 + align(alignment, widest, list_width),
+                1 + ascii_height + 1,
+            ),
+            1 + ascii_height + 1 + list_height,
+        ),
+        Position::Bottom => (
+            (
+                1 + align(alignment, widest, ascii_width),
+                1 + list_height + 1,
+            ),
+            (1 + align(alignment, widest, list_width), 1),
+            1 + list_height + 1 + ascii_height,
+        ),
+    };
+
+    if let Some(max_width) = max_width {
+        width = width.min(max_width);
+    }
+
+    // A box around everything wraps the area that is otherwise used, shifting what it holds.
+    let (dx, dy) = (outer_insets.x, outer_insets.y);
+    let mut tmp_buffer = Buffer::empty(Rect::new(
+        0,
+        0,
+        width + outer_insets.width,
+        height + outer_insets.height,
+    ));
+
+    if let Some(block) = the_box.as_ref().filter(|_| outer_insets.width > 0) {
+        let area = tmp_buffer.area;
+        let box_area = Rect::new(1, 1, area.width.saturating_sub(2), area.height - 1);
+        block.clone().render(box_area, &mut tmp_buffer);
+    }
+
+    let (ascii_x, ascii_y, list_x, list_y) = (ascii_x + dx, ascii_y + dy, list_x + dx, list_y + dy);
+
+    if let Some(block) = the_box.filter(|_| ascii_insets.width > 0) {
+        let box_area = Rect::new(ascii_x, ascii_y, ascii_width, ascii_height);
+        if box_area.intersects(tmp_buffer.area) {
+            block.render(box_area.intersection(tmp_buffer.area), &mut tmp_buffer);
+        }
+    }
+
+    let (art_x, art_y) = (ascii_x + ascii_insets.x, ascii_y + ascii_insets.y);
+    let ascii_area = match ascii {
+        Some(ascii) => draw_ascii(ascii, art_x, art_y, &mut tmp_buffer),
+        None => Rect::new(art_x, art_y, art_width, art_height),
+    };
+
+    // The readouts may use the rest of the line, unless the art is to their right.
+    let list_right = match position {
+        Position::Right if ascii_width > 0 => ascii_x - 2,
+        _ => dx + width.saturating_sub(1),
+    };
+    let readout_area = Rect::new(
+        list_x,
+        list_y,
+        list_right
+            .min(dx + width.saturating_sub(1))
+            .saturating_sub(list_x),
+        list_height,
+    );
+
+    if readout_area.width > 0 {
+        list.render(readout_area, &mut tmp_buffer);
+    }
+
+    Ok((tmp_buffer, ascii_area))
+}
+
+fn select_ascii(small: bool) -> Option<Text<'static>> {
+    let ascii_art = ascii::get_ascii_art(small);
+
+    if !ascii_art.is_empty() {
+        Some(ascii_art[0].to_owned())
+    } else {
+        None
+    }
+}
+
+/// Something that was asked for but couldn't be used, and was fallen back from.
+pub(crate) struct Warning {
+    message: String,
+    // "Reason" or "Suggestion"
+    label: &'static str,
+    detail: String,
+}
+
+impl Warning {
+    fn print(&self) {
+        println!("\x1b[33mWarning\x1b[0m: {}", self.message);
+        println!("\x1b[35m{}\x1b[m: {}", self.label, self.detail);
+    }
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Warning: {} {}: {}",
+            self.message, self.label, self.detail
+        )
+    }
+}
+
+pub(crate) fn create_theme(opt: &Opt) -> Theme {
+    let (theme, warnings) = load_theme(opt);
+    for warning in &warnings {
+        warning.print();
+    }
+
+    theme
+}
+
+/// Builds the theme asked for by `opt`, along with what went wrong while doing so, without
+/// printing anything.
+pub(crate) fn load_theme(opt: &Opt) -> (Theme, Vec<Warning>) {
+    let mut warnings = Vec::new();
+    let mut theme = Theme::default();
+
+    if let Some(opt_theme) = &opt.theme {
+        match Theme::get_theme(opt_theme) {
+            Ok(custom_theme) => theme = custom_theme,
+            Err(e) => {
+                let (label, detail) = match e.kind() {
+                    io::ErrorKind::NotFound => (
+                        "Suggestion",
+                        String::from("Perhaps the theme doesn't exist?"),
+                    ),
+                    _ => ("Reason", e.to_string()),
+                };
+                warnings.push(Warning {
+                    message: format!("Invalid theme \"{}\", falling back to default.", opt_theme),
+                    label,
+                    detail,
+                });
+            }
+        }
+    }
+
+    if let Some(names) = opt.ascii.as_ref().filter(|names| !names.is_empty()) {
+        let name = &names[rand::thread_rng().gen_range(0..names.len())];
+
+        match paths::find_file("ascii", name, "ascii") {
+            Some(path) => theme.set_custom_ascii_path(path),
+            None if ascii::get_builtin_ascii(name).is_some() => theme.set_builtin_ascii(name),
+            None => warnings.push(Warning {
+                message: format!(
+                    "Invalid ASCII art \"{}\", falling back to the theme's.",
+                    name
+                ),
+                label: "Suggestion",
+                detail: String::from("Run macchina --list-ascii to see what's available."),
+            }),
+        }
+    }
+
+    let color_variants = MacchinaColor::variants();
+    let random_palette = theme.get_random_palette();
+    let make_random_color = || {
+        let mut random = rand::thread_rng();
+        match &random_palette {
+            Some(palette) => palette[random.gen_range(0..palette.len())],
+            None => {
+                MacchinaColor::from_str(color_variants[random.gen_range(0..color_variants.len())])
+                    .unwrap()
+                    .get_color()
+            }
+        }
+    };
+
+    if theme.is_key_color_randomized() {
+        theme.set_key_color(make_random_color());
+    }
+
+    if theme.is_separator_color_randomized() {
+        theme.set_separator_color(make_random_color());
+    }
+
+    // Colors that are remapped explicitly are left alone.
+    if theme.are_ascii_colors_randomized() {
+        for (name, _) in ascii::ANSI_COLOR_NAMES.iter() {
+            let remapped = theme
+                .get_ascii_remap()
+                .keys()
+                .any(|key| key.eq_ignore_ascii_case(name));
+
+            if !remapped {
+                theme.set_ascii_remap(name, make_random_color());
+            }
+        }
+    }
+
+    if theme.are_bar_delimiters_hidden() {
+        theme.hide_bar_delimiters();
+    }
+
+    (theme, warnings)
+}
+
+pub(crate) fn should_display(opt: &Opt) -> Vec<ReadoutKey> {
+    if let Some(shown) = opt.show.to_owned() {
+        return shown;
+    }
+
+    let keys: Vec<ReadoutKey> = ReadoutKey::variants()
+        .iter()
+        .map(|f| ReadoutKey::from_str(f).unwrap())
+        .collect();
+
+    keys
+}
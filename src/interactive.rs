@@ -0,0 +1,257 @@
+use crate::cli::{ColorDepth, Opt};
+use crate::color;
+use crate::data::{self, Readout, ReadoutKey};
+use crate::fetch;
+use crate::paths;
+use crate::screen::AlternateScreen;
+use crate::theme::Theme;
+use crate::widgets::canvas::Canvas;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use std::io::{self, Stdout};
+use std::str::FromStr;
+use tui::backend::CrosstermBackend;
+use tui::layout::{Constraint, Direction, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph};
+use tui::{Frame, Terminal};
+
+const HELP: &str = " ↑/↓ move · space toggle · ←/→ theme · s save · q quit ";
+
+/// Holds the state of the interactive browser: which readouts are checked, which theme is
+/// being previewed and the readouts collected with that theme.
+struct Browser {
+    opt: Opt,
+    keys: Vec<(ReadoutKey, bool)>,
+    list_state: ListState,
+    themes: Vec<String>,
+    selected_theme: Option<usize>,
+    // The theme of the configuration, which might not be among the installed themes
+    configured_theme: Option<String>,
+    // Whether a theme was picked from the list, rather than the configured one being kept
+    theme_picked: bool,
+    theme: Theme,
+    readouts: Vec<Readout<'static>>,
+    status: String,
+    color_depth: ColorDepth,
+}
+
+impl Browser {
+    fn new(opt: &Opt) -> Self {
+        let shown = fetch::should_display(opt);
+        let keys = ReadoutKey::variants()
+            .iter()
+            .map(|f| ReadoutKey::from_str(f).unwrap())
+            .map(|key| (key, shown.contains(&key)))
+            .collect();
+
+        let themes = paths::installed_themes();
+        let selected_theme = opt
+            .theme
+            .as_ref()
+            .and_then(|name| themes.iter().position(|t| t == name));
+
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        let mut browser = Browser {
+            opt: opt.clone(),
+            keys,
+            list_state,
+            themes,
+            selected_theme,
+            configured_theme: opt.theme.clone(),
+            theme_picked: false,
+            theme: Theme::default(),
+            readouts: Vec::new(),
+            status: String::new(),
+            color_depth: opt.color_depth.unwrap_or_else(color::detect_color_depth),
+        };
+
+        browser.load_theme();
+        browser
+    }
+
+    fn theme_name(&self) -> Option<&str> {
+        match self.selected_theme {
+            Some(i) => Some(self.themes[i].as_str()),
+            None if !self.theme_picked => self.configured_theme.as_deref(),
+            None => None,
+        }
+    }
+
+    /// Loads the selected theme and re-collects every readout with it, since some readouts
+    /// (e.g. bars) depend on the theme. Problems with the theme end up in the status line.
+    fn load_theme(&mut self) {
+        self.opt.theme = self.theme_name().map(String::from);
+        let (theme, warnings) = fetch::load_theme(&self.opt);
+        self.theme = theme;
+        self.status = warnings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let all_keys = self.keys.iter().map(|(key, _)| *key).collect();
+        self.readouts = data::get_all_readouts(&self.opt, &self.theme, all_keys);
+    }
+
+    fn checked_keys(&self) -> Vec<ReadoutKey> {
+        self.keys
+            .iter()
+            .filter(|(_, checked)| *checked)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    fn move_selection(&mut self, forward: bool) {
+        let len = self.keys.len();
+        let current = self.list_state.selected().unwrap_or(0);
+        let next = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+
+        self.list_state.select(Some(next));
+    }
+
+    fn toggle_selected(&mut self) {
+        if let Some(i) = self.list_state.selected() {
+            self.keys[i].1 = !self.keys[i].1;
+        }
+    }
+
+    /// Cycles through the installed themes, `None` stands for the default theme.
+    fn cycle_theme(&mut self, forward: bool) {
+        let len = self.themes.len();
+        if len == 0 {
+            return;
+        }
+
+        self.selected_theme = match (self.selected_theme, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(len - 1),
+            (Some(i), true) if i + 1 < len => Some(i + 1),
+            (Some(i), false) if i > 0 => Some(i - 1),
+            _ => None,
+        };
+        self.theme_picked = true;
+
+        self.load_theme();
+    }
+
+    fn save(&mut self) {
+        let path = match self.opt.config.clone().or_else(Opt::config_path) {
+            Some(path) => path,
+            None => {
+                self.status = String::from("Could not locate the configuration file.");
+                return;
+            }
+        };
+
+        // The theme line is left alone unless another theme was picked.
+        let theme = self.theme_picked.then(|| self.theme_name());
+        self.status = match Opt::save_preferences(&path, &self.checked_keys(), theme) {
+            Ok(_) => format!("Saved to {}", path.to_string_lossy()),
+            Err(e) => String::from(e),
+        };
+    }
+
+    fn draw(&mut self, f: &mut Frame<CrosstermBackend<Stdout>>) -> io::Result<()> {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(28), Constraint::Min(0)])
+            .split(rows[0]);
+
+        let items: Vec<ListItem> = self
+            .keys
+            .iter()
+            .map(|(key, checked)| {
+                let mark = if *checked { "[x]" } else { "[ ]" };
+                ListItem::new(format!("{} {}", mark, key))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(" Readouts ")
+                    .border_type(BorderType::Rounded)
+                    .borders(Borders::ALL),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        f.render_stateful_widget(list, columns[0], &mut self.list_state);
+
+        let preview_block = Block::default()
+            .title(format!(
+                " Theme: {} ",
+                self.theme_name().unwrap_or("default")
+            ))
+            .border_type(BorderType::Rounded)
+            .borders(Borders::ALL);
+        let preview_area = preview_block.inner(columns[1]);
+        f.render_widget(preview_block, columns[1]);
+
+        let checked = self.checked_keys();
+        let readouts: Vec<Readout> = self
+            .readouts
+            .iter()
+            .filter(|r| checked.contains(&r.0))
+            .cloned()
+            .collect();
+
+        let (mut canvas, _) =
+            fetch::draw_fetch(readouts, &self.theme, Some(preview_area.width), None)?;
+        color::quantize_buffer(&mut canvas, self.color_depth);
+        f.render_widget(Canvas(&canvas), preview_area);
+
+        let footer = Spans::from(vec![
+            Span::styled(HELP, Style::default().fg(Color::Black).bg(Color::Blue)),
+            Span::raw(" "),
+            Span::raw(self.status.as_str()),
+        ]);
+        f.render_widget(Paragraph::new(footer), rows[1]);
+
+        Ok(())
+    }
+}
+
+fn run(terminal: &mut Terminal<CrosstermBackend<Stdout>>, opt: &Opt) -> io::Result<()> {
+    let mut browser = Browser::new(opt);
+
+    loop {
+        let mut result = Ok(());
+        terminal.draw(|f| result = browser.draw(f))?;
+        result?;
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(())
+                }
+                KeyCode::Up | KeyCode::Char('k') => browser.move_selection(false),
+                KeyCode::Down | KeyCode::Char('j') => browser.move_selection(true),
+                KeyCode::Char(' ') | KeyCode::Enter => browser.toggle_selected(),
+                KeyCode::Left | KeyCode::Char('h') => browser.cycle_theme(false),
+                KeyCode::Right | KeyCode::Char('l') => browser.cycle_theme(true),
+                KeyCode::Char('s') => browser.save(),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Lists every readout with a checkbox and previews the installed themes, the resulting `show`
+/// list and `theme` are written back to the configuration file upon saving.
+pub(crate) fn browse(opt: &Opt) -> io::Result<()> {
+    let mut screen = AlternateScreen::enter()?;
+    run(&mut screen, opt)
+}
@@ -1,12 +1,22 @@
+mod animation;
 mod bars;
+mod buffer;
 mod cli;
+mod color;
 mod config;
+mod export;
 mod extra;
+mod fetch;
 mod format;
+mod interactive;
+mod paths;
+mod picture;
+mod plain;
+mod screen;
 mod theme;
+mod watch;
 
-use cli::{MacchinaColor, Opt};
-use colored::Colorize;
+use cli::{ColorMode, Opt};
 use std::io;
 use structopt::StructOpt;
 
@@ -18,199 +28,19 @@ mod data;
 mod doctor;
 pub mod widgets;
 
-use crate::data::ReadoutKey;
-use crate::theme::Theme;
-use crate::widgets::readout::ReadoutList;
+use crate::animation::Animation;
+use crate::picture::Graphic;
 use atty::Stream;
-use data::Readout;
-use rand::Rng;
 use std::io::Stdout;
-use std::str::FromStr;
+use std::time::Duration;
 use tui::backend::{Backend, CrosstermBackend};
-use tui::buffer::{Buffer, Cell};
-use tui::layout::{Margin, Rect};
-use tui::text::Text;
-use tui::widgets::{Block, BorderType, Borders, Paragraph, Widget};
-use unicode_width::UnicodeWidthStr;
+use tui::buffer::Buffer;
 
 fn create_backend() -> CrosstermBackend<Stdout> {
     CrosstermBackend::new(io::stdout())
 }
 
-fn find_widest_cell(buf: &Buffer, last_y: u16) -> u16 {
-    let area = &buf.area;
-    let mut widest: u16 = 0;
-    let empty_cell = Cell::default();
-
-    for y in 0..last_y {
-        for x in (0..area.width).rev() {
-            let current_cell = buf.get(x, y);
-            if current_cell.ne(&empty_cell) && x > widest {
-                widest = x;
-                break;
-            }
-        }
-    }
-
-    widest + 1
-}
-
-fn find_last_buffer_cell_index(buf: &Buffer) -> Option<(u16, u16)> {
-    let empty_cell = Cell::default();
-
-    if let Some((idx, _)) = buf
-        .content
-        .iter()
-        .enumerate()
-        .filter(|p| !(*(p.1)).eq(&empty_cell))
-        .last()
-    {
-        return Some(buf.pos_of(idx));
-    }
-
-    None
-}
-
-fn draw_ascii(ascii: Text<'static>, tmp_buffer: &mut Buffer) -> Rect {
-    let ascii_rect = Rect {
-        x: 1,
-        y: 1,
-        width: ascii.width() as u16,
-        height: ascii.height() as u16,
-    };
-
-    Paragraph::new(ascii).render(ascii_rect, tmp_buffer);
-    ascii_rect
-}
-
-fn draw_readout_data(data: Vec<Readout>, theme: Theme, buf: &mut Buffer, area: Rect) {
-    let mut list = ReadoutList::new(data, &theme);
-
-    if theme.is_box_visible() {
-        list = list
-            .block_inner_margin(Margin {
-                horizontal: theme.get_horizontal_margin(),
-                vertical: theme.get_vertical_margin(),
-            })
-            .block(
-                Block::default()
-                    .border_type(BorderType::Rounded)
-                    .title(theme.get_box_title())
-                    .borders(Borders::ALL),
-            );
-    }
-
-    list.render(area, buf);
-}
-
-fn create_theme(opt: &Opt) -> Theme {
-    let mut found = false;
-    let mut theme = Theme::default();
-    let dirs = [dirs::config_dir(), libmacchina::extra::localbase_dir()];
-
-    if let Some(opt_theme) = &opt.theme {
-        for dir in dirs {
-            if let Ok(custom_theme) = Theme::get_theme(opt_theme, dir) {
-                found = true;
-                theme = Theme::from(custom_theme);
-            }
-        }
-
-        if !found {
-            println!(
-                "\x1b[33mWarning\x1b[0m: Invalid theme \"{}\", falling back to default.",
-                opt_theme
-            );
-            println!("\x1b[35mSuggestion\x1b[m: Perhaps the theme doesn't exist?");
-        }
-    }
-
-    let color_variants = MacchinaColor::variants();
-    let make_random_color = || {
-        let mut random = rand::thread_rng();
-        MacchinaColor::from_str(color_variants[random.gen_range(0..color_variants.len())])
-            .unwrap()
-            .get_color()
-    };
-
-    if theme.is_key_color_randomized() {
-        theme.set_key_color(make_random_color());
-    }
-
-    if theme.is_separator_color_randomized() {
-        theme.set_separator_color(make_random_color());
-    }
-
-    if theme.are_bar_delimiters_hidden() {
-        theme.hide_bar_delimiters();
-    }
-
-    theme
-}
-
-fn should_display(opt: &Opt) -> Vec<ReadoutKey> {
-    if let Some(shown) = opt.show.to_owned() {
-        return shown;
-    }
-
-    let keys: Vec<ReadoutKey> = ReadoutKey::variants()
-        .iter()
-        .map(|f| ReadoutKey::from_str(f).unwrap())
-        .collect();
-
-    keys
-}
-
-fn select_ascii(small: bool) -> Option<Text<'static>> {
-    let ascii_art = ascii::get_ascii_art(small);
-
-    if !ascii_art.is_empty() {
-        Some(ascii_art[0].to_owned())
-    } else {
-        None
-    }
-}
-
-fn list_themes() {
-    let dirs = [dirs::config_dir(), libmacchina::extra::localbase_dir()];
-    for i in dirs {
-        if let Some(dir) = i {
-            let entries = libmacchina::extra::list_dir_entries(&dir.join("macchina/themes"));
-            if !entries.is_empty() {
-                let custom_themes = entries.iter().filter(|&x| {
-                    if let Some(ext) = libmacchina::extra::path_extension(&x) {
-                        ext == "toml"
-                    } else {
-                        false
-                    }
-                });
-
-                if custom_themes.clone().count() == 0 {
-                    println!(
-                        "\nNo custom themes were found in {}",
-                        dir.join("macchina/themes")
-                            .to_string_lossy()
-                            .bright_yellow()
-                    )
-                }
-
-                custom_themes.for_each(|x| {
-                    if let Some(theme) = x.file_name() {
-                        let name = theme.to_string_lossy().replace(".toml", "");
-                        println!(
-                            "- {} ({}/macchina/themes)",
-                            name.bright_green(),
-                            &dir.to_string_lossy()
-                        );
-                    }
-                });
-            }
-        }
-    }
-}
-
 fn main() -> Result<(), io::Error> {
-    let opt: Opt;
     let arg_opt = Opt::from_args();
 
     if arg_opt.export_config {
@@ -218,20 +48,22 @@ fn main() -> Result<(), io::Error> {
         return Ok(());
     }
 
-    let config_opt;
-    if arg_opt.config.is_some() {
-        config_opt = Opt::from_config_file(&arg_opt.config.clone().unwrap());
+    let config_opt = if arg_opt.config.is_some() {
+        Opt::from_config_file(&arg_opt.config.clone().unwrap())
     } else {
-        config_opt = Opt::from_config();
-    }
+        Opt::from_config()
+    };
 
-    if let Ok(mut config_opt) = config_opt {
-        config_opt.patch_args(Opt::from_args());
-        opt = config_opt;
-    } else {
-        println!("\x1b[33mWarning:\x1b[0m {}", config_opt.unwrap_err());
-        opt = arg_opt;
-    }
+    let opt = match config_opt {
+        Ok(mut config_opt) => {
+            config_opt.patch_args(Opt::from_args());
+            config_opt
+        }
+        Err(e) => {
+            println!("\x1b[33mWarning:\x1b[0m {}", e);
+            arg_opt
+        }
+    };
 
     if opt.version {
         if let Some(git_sha) = option_env!("VERGEN_GIT_SHA_SHORT") {
@@ -244,7 +76,7 @@ fn main() -> Result<(), io::Error> {
     }
 
     if opt.list_themes {
-        list_themes();
+        paths::list_themes();
         return Ok(());
     }
 
@@ -253,8 +85,19 @@ fn main() -> Result<(), io::Error> {
         return Ok(());
     }
 
-    let theme = create_theme(&opt);
-    let should_display = should_display(&opt);
+    if opt.list_ascii {
+        paths::list_ascii();
+        return Ok(());
+    }
+
+    if opt.interactive {
+        return interactive::browse(&opt);
+    }
+
+    let export_target = opt.export_target().unwrap_or_else(|e| e.exit());
+
+    let theme = fetch::create_theme(&opt);
+    let should_display = fetch::should_display(&opt);
     let readout_data = data::get_all_readouts(&opt, &theme, should_display);
 
     if opt.doctor {
@@ -262,62 +105,72 @@ fn main() -> Result<(), io::Error> {
         return Ok(());
     }
 
-    let mut backend = create_backend();
-    let mut tmp_buffer = Buffer::empty(Rect::new(0, 0, 500, 50));
-
-    let ascii_area;
-
-    if let Some(ref file_path) = theme.get_custom_ascii_path() {
-        let file_path = extra::expand_home(file_path).expect("Failed to expand ~ to HOME");
-        let ascii_art;
-        match theme.using_custom_ascii_color() {
-            true => {
-                ascii_art = ascii::get_ascii_from_file_override_color(
-                    &file_path,
-                    theme.get_custom_ascii_color(),
-                )?;
-            }
-            false => {
-                ascii_art = ascii::get_ascii_from_file(&file_path)?;
-            }
-        };
+    if let Some(interval) = opt.watch {
+        return watch::watch(&opt, &theme, readout_data, Duration::from_secs(interval));
+    }
 
-        // If the file is empty just default to disabled
-        if ascii_art.width() != 0 && ascii_art.height() < 50 && !theme.is_ascii_hidden() {
-            // because tmp_buffer height is 50
-            ascii_area = draw_ascii(ascii_art.to_owned(), &mut tmp_buffer);
-        } else {
-            ascii_area = Rect::new(0, 1, 0, tmp_buffer.area.height - 1);
-        }
-    } else if readout_data.len() <= 6 || theme.prefers_small_ascii() {
-        // prefer smaller ascii if condition is satisfied
-        ascii_area = match (theme.is_ascii_hidden(), select_ascii(true)) {
-            (false, Some(ascii)) => draw_ascii(ascii.to_owned(), &mut tmp_buffer),
-            _ => Rect::new(0, 1, 0, tmp_buffer.area.height - 1),
-        };
+    if let Some((format, path)) = export_target {
+        let (tmp_buffer, _) = fetch::draw_fetch(readout_data, &theme, None, None)?;
+        return export::export(&tmp_buffer, format, &path, theme.get_export_background());
+    }
+
+    let mut backend = create_backend();
+    let is_tty = atty::is(Stream::Stdout);
+    let color = opt.color.unwrap_or(ColorMode::Auto).is_enabled(is_tty);
+    let color_depth = opt.color_depth.unwrap_or_else(color::detect_color_depth);
+    let max_width = if is_tty {
+        backend
+            .size()
+            .ok()
+            .map(|size| size.width)
+            .filter(|width| *width > 0)
     } else {
-        // prefer bigger ascii
-        ascii_area = match (theme.is_ascii_hidden(), select_ascii(false)) {
-            (false, Some(ascii)) => draw_ascii(ascii.to_owned(), &mut tmp_buffer),
-            _ => Rect::new(0, 1, 0, tmp_buffer.area.height - 1),
+        None
+    };
+
+    // Images and animations need to fit next to the readouts, otherwise they're drawn as static
+    // text like in every other output mode.
+    let fits = |(width, _): (u16, u16)| !matches!(max_width, Some(max) if max <= width + 3);
+    let (graphic, animation) = match is_tty && color {
+        true => (
+            fetch::select_fetch_graphic(&theme)?.filter(|g| fits(g.size())),
+            fetch::select_fetch_animation(&theme, color_depth)?.filter(|a| fits(a.size())),
+        ),
+        false => (None, None),
+    };
+
+    let reserved_size = graphic
+        .as_ref()
+        .map(Graphic::size)
+        .or_else(|| animation.as_ref().map(Animation::size));
+
+    let (mut tmp_buffer, ascii_area) =
+        fetch::draw_fetch(readout_data, &theme, max_width, reserved_size)?;
+    color::quantize_buffer(&mut tmp_buffer, color_depth);
+
+    // Cursor positioning only makes sense on a terminal, everything else gets plain lines.
+    if !is_tty || !color {
+        return match plain::write_buffer(&mut io::stdout(), &tmp_buffer, color) {
+            // The reader went away (e.g. `macchina | head`), there's nobody left to complain to.
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => result,
         };
     }
 
-    let tmp_buffer_area = tmp_buffer.area;
-
-    draw_readout_data(
-        readout_data,
-        theme,
-        &mut tmp_buffer,
-        Rect::new(
-            ascii_area.x + ascii_area.width + 2,
-            ascii_area.y,
-            tmp_buffer_area.width - ascii_area.width - 4,
-            ascii_area.height,
-        ),
-    );
+    let reserved_last_y = match reserved_size {
+        Some(_) => ascii_area.bottom().saturating_sub(1),
+        None => 0,
+    };
+    let starting_pos = write_buffer_to_console(&mut backend, &mut tmp_buffer, reserved_last_y)?;
+    let (ascii_x, ascii_y) = (ascii_area.x, starting_pos + ascii_area.y);
+
+    if let Some(graphic) = graphic {
+        draw_graphic(&mut backend, &graphic, ascii_x, ascii_y)?;
+    }
 
-    write_buffer_to_console(&mut backend, &mut tmp_buffer)?;
+    if let Some(animation) = animation {
+        animation.play(&mut backend, ascii_x, ascii_y)?;
+    }
 
     backend.flush()?;
     print!("\n\n");
@@ -325,16 +178,20 @@ fn main() -> Result<(), io::Error> {
     Ok(())
 }
 
+/// Writes the buffer below the cursor and returns the row of the terminal its first line
+/// ended up on. Every line up to `reserved_last_y` is written, even if it's blank.
 fn write_buffer_to_console(
     backend: &mut CrosstermBackend<Stdout>,
     tmp_buffer: &mut Buffer,
-) -> Result<(), io::Error> {
+    reserved_last_y: u16,
+) -> Result<u16, io::Error> {
     let term_size = backend.size().unwrap_or_default();
 
-    let (_, last_y) =
-        find_last_buffer_cell_index(tmp_buffer).expect("Error while writing to terminal buffer.");
+    let last_y = buffer::find_last_buffer_cell_index(tmp_buffer)
+        .map(|(_, y)| y.max(reserved_last_y))
+        .expect("Error while writing to terminal buffer.");
 
-    let last_x = find_widest_cell(tmp_buffer, last_y);
+    let last_x = buffer::find_widest_cell(tmp_buffer, last_y);
 
     print!("{}", "\n".repeat(last_y as usize + 1));
 
@@ -346,29 +203,30 @@ fn write_buffer_to_console(
     // We need a checked subtraction here, because (cursor_y - last_y - 1) might underflow if the
     // cursor_y is smaller than (last_y - 1).
     let starting_pos = cursor_y.saturating_sub(last_y).saturating_sub(1);
-    let mut skip_n = 0;
-
-    let iter = tmp_buffer
-        .content
-        .iter()
-        .enumerate()
-        .filter(|(_previous, cell)| {
-            let curr_width = cell.symbol.width();
-            if curr_width == 0 {
-                return false;
-            }
-
-            let old_skip = skip_n;
-            skip_n = curr_width.saturating_sub(1);
-            old_skip == 0
-        })
-        .map(|(idx, cell)| {
-            let (x, y) = tmp_buffer.pos_of(idx);
-            (x, y, cell)
-        })
+
+    let iter = buffer::visible_cells(tmp_buffer)
         .filter(|(x, y, _)| *x < last_x && *x < term_size.width && *y <= last_y)
         .map(|(x, y, cell)| (x, y + starting_pos, cell));
 
     backend.draw(iter)?;
-    Ok(())
+    Ok(starting_pos)
+}
+
+/// Draws `graphic` over the area `draw_fetch` left blank for it, whose top left corner is at `x`
+/// and `y` on the terminal.
+fn draw_graphic(
+    backend: &mut CrosstermBackend<Stdout>,
+    graphic: &Graphic,
+    x: u16,
+    y: u16,
+) -> io::Result<()> {
+    backend.flush()?;
+    let (cursor_x, cursor_y) = backend.get_cursor()?;
+
+    backend.set_cursor(x, y)?;
+    graphic.write(backend)?;
+    backend.flush()?;
+
+    // Sixel graphics move the cursor below the image.
+    backend.set_cursor(cursor_x, cursor_y)
 }
@@ -0,0 +1,94 @@
+use crate::ascii;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+/// Returns the directories that hold `macchina/themes` and `macchina/ascii`, the one that takes
+/// precedence first: the user's configuration directory overrides the system's.
+fn data_dirs() -> Vec<PathBuf> {
+    vec![dirs::config_dir(), libmacchina::extra::localbase_dir()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Returns the path of `<dir>/macchina/<subdir>/<name>.<extension>` in the first of the
+/// `data_dirs` that has it.
+pub(crate) fn find_file(subdir: &str, name: &str, extension: &str) -> Option<PathBuf> {
+    data_dirs()
+        .into_iter()
+        .map(|dir| {
+            dir.join("macchina")
+                .join(subdir)
+                .join(format!("{}.{}", name, extension))
+        })
+        .find(|path| path.is_file())
+}
+
+/// Returns the names of the files with the given extension found in `dir/macchina/<subdir>`.
+fn find_files(dir: &Path, subdir: &str, extension: &str) -> Vec<String> {
+    libmacchina::extra::list_dir_entries(&dir.join("macchina").join(subdir))
+        .iter()
+        .filter(|&x| {
+            if let Some(ext) = libmacchina::extra::path_extension(x) {
+                ext == extension
+            } else {
+                false
+            }
+        })
+        .filter_map(|x| x.file_stem())
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Returns the names of the themes found in `dir/macchina/themes`.
+fn find_themes(dir: &Path) -> Vec<String> {
+    find_files(dir, "themes", "toml")
+}
+
+/// Returns the names of all the themes macchina can find, without duplicates.
+pub(crate) fn installed_themes() -> Vec<String> {
+    let mut themes: Vec<String> = data_dirs().iter().flat_map(|d| find_themes(d)).collect();
+    themes.sort();
+    themes.dedup();
+    themes
+}
+
+pub(crate) fn list_themes() {
+    for dir in data_dirs() {
+        if libmacchina::extra::list_dir_entries(&dir.join("macchina/themes")).is_empty() {
+            continue;
+        }
+
+        let custom_themes = find_themes(&dir);
+        if custom_themes.is_empty() {
+            println!(
+                "\nNo custom themes were found in {}",
+                dir.join("macchina/themes")
+                    .to_string_lossy()
+                    .bright_yellow()
+            )
+        }
+
+        for name in custom_themes {
+            println!(
+                "- {} ({}/macchina/themes)",
+                name.bright_green(),
+                &dir.to_string_lossy()
+            );
+        }
+    }
+}
+
+pub(crate) fn list_ascii() {
+    ascii::list_builtin_ascii();
+
+    for dir in data_dirs() {
+        for name in find_files(&dir, "ascii", "ascii") {
+            println!(
+                "- {} ({}/macchina/ascii)",
+                name.bright_green(),
+                &dir.to_string_lossy()
+            );
+        }
+    }
+}
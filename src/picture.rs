@@ -0,0 +1,454 @@
+use crate::cli::ColorDepth;
+use crate::color::{nearest, palette};
+use crate::theme::{GraphicsProtocol, Image, ImageStyle};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans, Text};
+
+/// The characters used by `ImageStyle::ASCII`, from the darkest to the brightest.
+const RAMP: &[u8] = b" .:-=+*#%@";
+
+/// Pixels that are more transparent than this are left blank.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// The size of a terminal cell in pixels, when the terminal doesn't report it.
+const DEFAULT_CELL_SIZE: (u32, u32) = (10, 20);
+
+/// Returns `true` if `path` has the extension of an image format that can be converted to text.
+pub fn is_image(path: &Path) -> bool {
+    matches!(
+        ImageFormat::from_path(path),
+        Ok(ImageFormat::Png) | Ok(ImageFormat::Jpeg)
+    )
+}
+
+fn open(path: &Path) -> io::Result<DynamicImage> {
+    image::open(path).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.to_string_lossy(), e),
+        )
+    })
+}
+
+/// Returns the number of rows `img` spans when it is `width` columns wide.
+fn rows(img: &DynamicImage, width: u16) -> u16 {
+    // Terminal cells are about twice as tall as they are wide.
+    (img.height() as f32 * width as f32 / img.width().max(1) as f32 / 2.0)
+        .round()
+        .clamp(1.0, u16::MAX as f32) as u16
+}
+
+/// Decodes the image at `path` and scales it down to `width` pixels, `cell_height` is the
+/// number of pixels that end up in a single row of text.
+fn load(path: &Path, width: u16, cell_height: u32) -> io::Result<RgbaImage> {
+    let img = open(path)?;
+    let width = width.max(1);
+    let height = u32::from(rows(&img, width)) * cell_height;
+
+    Ok(img
+        .resize_exact(u32::from(width), height, FilterType::Triangle)
+        .to_rgba8())
+}
+
+/// Reduces every pixel of `img` to the given color depth, optionally spreading the error with
+/// Floyd-Steinberg dithering. Transparent pixels are `None`.
+fn quantize(img: &RgbaImage, depth: ColorDepth, dither: bool) -> Vec<Option<Color>> {
+    let palette = palette(depth);
+    let width = img.width() as usize;
+    let height = img.height() as usize;
+    let mut errors = vec![[0f32; 3]; width * height];
+    let mut colors = Vec::with_capacity(width * height);
+
+    for (i, pixel) in img.pixels().enumerate() {
+        let [r, g, b, a] = pixel.0;
+        if a < ALPHA_THRESHOLD {
+            colors.push(None);
+            continue;
+        }
+
+        if palette.is_empty() {
+            colors.push(Some(Color::Rgb(r, g, b)));
+            continue;
+        }
+
+        let wanted = [
+            r as f32 + errors[i][0],
+            g as f32 + errors[i][1],
+            b as f32 + errors[i][2],
+        ];
+        let (color, (pr, pg, pb)) = palette[nearest(&palette, wanted)];
+        colors.push(Some(color));
+
+        if !dither {
+            continue;
+        }
+
+        let error = [
+            wanted[0] - pr as f32,
+            wanted[1] - pg as f32,
+            wanted[2] - pb as f32,
+        ];
+        let (x, y) = (i % width, i / width);
+        let neighbours = [
+            (x + 1, y, 7.0 / 16.0),
+            (x.wrapping_sub(1), y + 1, 3.0 / 16.0),
+            (x, y + 1, 5.0 / 16.0),
+            (x + 1, y + 1, 1.0 / 16.0),
+        ];
+
+        for (nx, ny, weight) in neighbours.iter() {
+            if *nx < width && *ny < height {
+                let j = ny * width + nx;
+                for c in 0..3 {
+                    errors[j][c] += error[c] * weight;
+                }
+            }
+        }
+    }
+
+    colors
+}
+
+/// Appends `symbol` to `spans`, extending the last span if it shares the same style.
+fn push(spans: &mut Vec<Span<'static>>, symbol: char, style: Style) {
+    match spans.last_mut() {
+        Some(span) if span.style == style => span.content.to_mut().push(symbol),
+        _ => spans.push(Span::styled(symbol.to_string(), style)),
+    }
+}
+
+/// Draws two pixels per cell: the upper one as the foreground of `▀` and the lower one as its
+/// background.
+fn to_blocks(colors: &[Option<Color>], width: usize) -> Text<'static> {
+    let rows: Vec<&[Option<Color>]> = colors.chunks(width).collect();
+    let mut lines = Vec::with_capacity(rows.len().div_ceil(2));
+
+    for pair in rows.chunks(2) {
+        let mut spans = Vec::new();
+
+        for x in 0..width {
+            let top = pair[0][x];
+            let bottom = pair.get(1).and_then(|row| row[x]);
+
+            match (top, bottom) {
+                (Some(top), Some(bottom)) => {
+                    push(&mut spans, '▀', Style::default().fg(top).bg(bottom))
+                }
+                (Some(top), None) => push(&mut spans, '▀', Style::default().fg(top)),
+                (None, Some(bottom)) => push(&mut spans, '▄', Style::default().fg(bottom)),
+                (None, None) => push(&mut spans, ' ', Style::default()),
+            }
+        }
+
+        lines.push(Spans::from(spans));
+    }
+
+    Text::from(lines)
+}
+
+/// Draws one pixel per cell, picking a character from `RAMP` according to its brightness.
+fn to_ascii(
+    img: &RgbaImage,
+    colors: &[Option<Color>],
+    color_override: Option<Color>,
+) -> Text<'static> {
+    let width = img.width() as usize;
+    let mut lines = Vec::with_capacity(img.height() as usize);
+
+    for (y, row) in colors.chunks(width).enumerate() {
+        let mut spans = Vec::new();
+
+        for (x, color) in row.iter().enumerate() {
+            let color = match color {
+                Some(color) => color_override.unwrap_or(*color),
+                None => {
+                    push(&mut spans, ' ', Style::default());
+                    continue;
+                }
+            };
+
+            let [r, g, b, _] = img.get_pixel(x as u32, y as u32).0;
+            let luma = (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0;
+            let index = (luma * (RAMP.len() - 1) as f32).round() as usize;
+            push(&mut spans, RAMP[index] as char, Style::default().fg(color));
+        }
+
+        lines.push(Spans::from(spans));
+    }
+
+    Text::from(lines)
+}
+
+/// Converts the PNG or JPEG at `path` into text, following the `custom_ascii.image` options.
+/// `color_override` replaces the colors of `ImageStyle::ASCII` art.
+pub fn get_ascii_from_image(
+    path: &Path,
+    options: &Image,
+    color_override: Option<Color>,
+) -> Result<Text<'static>, io::Error> {
+    match options.style {
+        ImageStyle::Blocks => {
+            let img = load(path, options.width, 2)?;
+            let colors = quantize(&img, options.colors, options.dither);
+            Ok(to_blocks(&colors, img.width() as usize))
+        }
+        ImageStyle::ASCII => {
+            let img = load(path, options.width, 1)?;
+            let colors = quantize(&img, options.colors, options.dither);
+            Ok(to_ascii(&img, &colors, color_override))
+        }
+    }
+}
+
+/// A way of drawing actual images in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Protocol {
+    Kitty,
+    Sixel,
+}
+
+/// Guesses which graphics protocol the terminal supports from its environment variables.
+fn detect_protocol() -> Option<Protocol> {
+    let var = |name| env::var(name).unwrap_or_default();
+    let term = var("TERM");
+    let program = var("TERM_PROGRAM");
+
+    // Multiplexers only pass the escape sequences through when they're wrapped, if at all.
+    if env::var_os("TMUX").is_some() || term.starts_with("screen") {
+        return None;
+    }
+
+    if env::var_os("KITTY_WINDOW_ID").is_some()
+        || term == "xterm-kitty"
+        || term == "xterm-ghostty"
+        || program == "WezTerm"
+        || program == "ghostty"
+    {
+        return Some(Protocol::Kitty);
+    }
+
+    if term.contains("sixel")
+        || term.starts_with("foot")
+        || term.starts_with("mlterm")
+        || term.starts_with("yaft")
+        || program == "mintty"
+    {
+        return Some(Protocol::Sixel);
+    }
+
+    None
+}
+
+/// Returns the size of a terminal cell in pixels.
+#[cfg(unix)]
+fn cell_size() -> (u32, u32) {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let found = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+
+    if found && size.ws_col > 0 && size.ws_row > 0 && size.ws_xpixel > 0 && size.ws_ypixel > 0 {
+        return (
+            u32::from(size.ws_xpixel / size.ws_col),
+            u32::from(size.ws_ypixel / size.ws_row),
+        );
+    }
+
+    DEFAULT_CELL_SIZE
+}
+
+/// Returns the size of a terminal cell in pixels.
+#[cfg(not(unix))]
+fn cell_size() -> (u32, u32) {
+    DEFAULT_CELL_SIZE
+}
+
+/// Transmits `img` as raw RGBA data and places it over `columns` by `rows` cells, without
+/// moving the cursor.
+fn write_kitty<W: Write>(out: &mut W, img: &RgbaImage, columns: u16, rows: u16) -> io::Result<()> {
+    let payload = base64::encode(img.as_raw());
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(4096).collect();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+
+        if i == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=32,s={},v={},c={},r={},C=1,q=2,m={};",
+                img.width(),
+                img.height(),
+                columns,
+                rows,
+                more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+
+    Ok(())
+}
+
+/// Writes `count` repetitions of the sixel holding `bits`, using a repeat introducer when it's
+/// shorter.
+fn write_sixel_run<W: Write>(out: &mut W, bits: u8, count: usize) -> io::Result<()> {
+    let sixel = (63 + bits) as char;
+
+    if count > 3 {
+        write!(out, "!{}{}", count, sixel)
+    } else {
+        write!(out, "{}", sixel.to_string().repeat(count))
+    }
+}
+
+/// Encodes `img` as Sixel graphics with the 256-color palette, transparent pixels are left
+/// untouched.
+fn write_sixel<W: Write>(out: &mut W, img: &RgbaImage) -> io::Result<()> {
+    let palette = palette(ColorDepth::Ansi256);
+    let width = img.width() as usize;
+    let height = img.height() as usize;
+    let mut cache = HashMap::new();
+
+    let indices: Vec<Option<usize>> = img
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            if a < ALPHA_THRESHOLD {
+                return None;
+            }
+
+            Some(
+                *cache
+                    .entry((r, g, b))
+                    .or_insert_with(|| nearest(&palette, [r as f32, g as f32, b as f32])),
+            )
+        })
+        .collect();
+
+    write!(out, "\x1bP0;1;0q\"1;1;{};{}", width, height)?;
+
+    let mut used: Vec<usize> = cache.values().copied().collect();
+    used.sort_unstable();
+    used.dedup();
+
+    for i in &used {
+        let (r, g, b) = palette[*i].1;
+        write!(
+            out,
+            "#{};2;{};{};{}",
+            i,
+            u32::from(r) * 100 / 255,
+            u32::from(g) * 100 / 255,
+            u32::from(b) * 100 / 255
+        )?;
+    }
+
+    // Every band is six pixels tall, and is drawn once for each of the colors it contains.
+    for top in (0..height).step_by(6) {
+        if top > 0 {
+            write!(out, "-")?;
+        }
+
+        let band = &indices[top * width..(top + 6).min(height) * width];
+        let mut colors: Vec<usize> = band.iter().flatten().copied().collect();
+        colors.sort_unstable();
+        colors.dedup();
+
+        for (n, color) in colors.iter().enumerate() {
+            if n > 0 {
+                write!(out, "$")?;
+            }
+
+            write!(out, "#{}", color)?;
+            let mut run = (0, 0);
+
+            for x in 0..width {
+                let bits = band
+                    .chunks(width)
+                    .enumerate()
+                    .filter(|(_, row)| row[x] == Some(*color))
+                    .fold(0, |bits, (dy, _)| bits | 1 << dy);
+
+                if bits != run.0 && run.1 > 0 {
+                    write_sixel_run(out, run.0, run.1)?;
+                    run.1 = 0;
+                }
+
+                run = (bits, run.1 + 1);
+            }
+
+            write_sixel_run(out, run.0, run.1)?;
+        }
+    }
+
+    write!(out, "\x1b\\")
+}
+
+/// An image drawn by the terminal itself in place of the ASCII art.
+pub struct Graphic {
+    protocol: Protocol,
+    image: RgbaImage,
+    columns: u16,
+    rows: u16,
+}
+
+impl Graphic {
+    /// Loads the PNG or JPEG at `path` if the terminal can draw it, otherwise `None` is returned
+    /// and the image should be converted to text with `get_ascii_from_image`.
+    pub fn new(path: &Path, options: &Image) -> io::Result<Option<Graphic>> {
+        let protocol = match options.protocol {
+            GraphicsProtocol::Auto => detect_protocol(),
+            GraphicsProtocol::Kitty => Some(Protocol::Kitty),
+            GraphicsProtocol::Sixel => Some(Protocol::Sixel),
+            GraphicsProtocol::None => None,
+        };
+
+        let protocol = match protocol {
+            Some(protocol) => protocol,
+            None => return Ok(None),
+        };
+
+        let img = open(path)?;
+        let columns = options.width.max(1);
+        let rows = rows(&img, columns);
+
+        // Sixel images aren't scaled by the terminal, so they have to be resized to fit their
+        // cells, which also keeps the escape sequences of either protocol short.
+        let (cell_width, cell_height) = cell_size();
+        let image = img
+            .resize(
+                u32::from(columns) * cell_width,
+                u32::from(rows) * cell_height,
+                FilterType::Triangle,
+            )
+            .to_rgba8();
+
+        Ok(Some(Graphic {
+            protocol,
+            image,
+            columns,
+            rows,
+        }))
+    }
+
+    /// Returns the number of columns and rows covered by the image.
+    pub fn size(&self) -> (u16, u16) {
+        (self.columns, self.rows)
+    }
+
+    /// Draws the image with its top left corner at the cursor.
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match self.protocol {
+            Protocol::Kitty => write_kitty(out, &self.image, self.columns, self.rows),
+            Protocol::Sixel => write_sixel(out, &self.image),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::buffer;
+use std::io::{self, Write};
+use tui::buffer::{Buffer, Cell};
+use tui::style::{Color, Modifier};
+
+/// Returns the SGR parameters that select `color`, as a foreground color if `fg` is `true` and as
+/// a background color otherwise.
+fn color_to_sgr(color: Color, fg: bool) -> String {
+    let (base, bright) = if fg { (30, 90) } else { (40, 100) };
+
+    match color {
+        Color::Reset => (base + 9).to_string(),
+        Color::Black => base.to_string(),
+        Color::Red => (base + 1).to_string(),
+        Color::Green => (base + 2).to_string(),
+        Color::Yellow => (base + 3).to_string(),
+        Color::Blue => (base + 4).to_string(),
+        Color::Magenta => (base + 5).to_string(),
+        Color::Cyan => (base + 6).to_string(),
+        Color::Gray => (base + 7).to_string(),
+        Color::DarkGray => bright.to_string(),
+        Color::LightRed => (bright + 1).to_string(),
+        Color::LightGreen => (bright + 2).to_string(),
+        Color::LightYellow => (bright + 3).to_string(),
+        Color::LightBlue => (bright + 4).to_string(),
+        Color::LightMagenta => (bright + 5).to_string(),
+        Color::LightCyan => (bright + 6).to_string(),
+        Color::White => (bright + 7).to_string(),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+        Color::Indexed(i) => format!("{};5;{}", base + 8, i),
+    }
+}
+
+/// Returns the SGR escape sequence that switches the terminal to the style of `cell`.
+fn cell_to_sgr(cell: &Cell) -> String {
+    let mut params = vec![String::from("0")];
+
+    let modifiers = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::SLOW_BLINK, "5"),
+        (Modifier::RAPID_BLINK, "6"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::HIDDEN, "8"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+
+    for (modifier, param) in modifiers.iter() {
+        if cell.modifier.contains(*modifier) {
+            params.push(String::from(*param));
+        }
+    }
+
+    if cell.fg != Color::Reset {
+        params.push(color_to_sgr(cell.fg, true));
+    }
+
+    if cell.bg != Color::Reset {
+        params.push(color_to_sgr(cell.bg, false));
+    }
+
+    format!("\x1b[{}m", params.join(";"))
+}
+
+/// Returns the visible cells of every row of `buf` that has content, see
+/// `buffer::visible_cells`. Trailing empty cells and rows are left out.
+pub(crate) fn visible_rows(buf: &Buffer) -> Vec<Vec<(u16, &Cell)>> {
+    let empty_cell = Cell::default();
+    let last_y = match buffer::find_last_buffer_cell_index(buf) {
+        Some((_, y)) => y,
+        None => return Vec::new(),
+    };
+
+    let mut rows = vec![Vec::new(); (last_y - buf.area.top() + 1) as usize];
+    for (x, y, cell) in buffer::visible_cells(buf).filter(|(_, y, _)| *y <= last_y) {
+        rows[(y - buf.area.top()) as usize].push((x, cell));
+    }
+
+    for cells in rows.iter_mut() {
+        let len = cells
+            .iter()
+            .rposition(|(_, cell)| **cell != empty_cell)
+            .map_or(0, |i| i + 1);
+        cells.truncate(len);
+    }
+
+    rows
+}
+
+/// Writes the content of `buf` line by line, without relying on cursor movement, which makes
+/// it suitable for pipes and other non-TTY consumers. Colors are emitted as SGR sequences if
+/// `color` is `true`.
+pub(crate) fn write_buffer<W: Write>(out: &mut W, buf: &Buffer, color: bool) -> io::Result<()> {
+    let reset = cell_to_sgr(&Cell::default());
+
+    // The fetch is drawn one row below the top, which only matters on a terminal.
+    let rows = visible_rows(buf)
+        .into_iter()
+        .skip_while(|cells| cells.is_empty());
+
+    for cells in rows {
+        let mut line = String::new();
+        let mut current_style = reset.clone();
+
+        for (_, cell) in cells {
+            if color {
+                let style = cell_to_sgr(cell);
+                if style != current_style {
+                    line.push_str(&style);
+                    current_style = style;
+                }
+            }
+
+            line.push_str(&cell.symbol);
+        }
+
+        if current_style != reset {
+            line.push_str(&reset);
+        }
+
+        writeln!(out, "{}", line)?;
+    }
+
+    Ok(())
+}
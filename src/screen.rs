@@ -0,0 +1,97 @@
+use crossterm::cursor::{Hide, Show};
+use crossterm::event::{self, Event, KeyEvent};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use std::io::{self, Stdout};
+use std::ops::{Deref, DerefMut};
+use std::time::Instant;
+use tui::backend::CrosstermBackend;
+use tui::Terminal;
+
+/// A terminal in raw mode on the alternate screen, which is restored once this is dropped, be
+/// it on success, on an error or while a panic unwinds.
+pub struct AlternateScreen(Terminal<CrosstermBackend<Stdout>>);
+
+impl AlternateScreen {
+    pub fn enter() -> io::Result<Self> {
+        // The guard exists before anything is changed, so that a failure half way through
+        // still restores whatever was set up.
+        let mut screen = AlternateScreen(Terminal::new(CrosstermBackend::new(io::stdout()))?);
+        execute!(screen.backend_mut(), EnterAlternateScreen)?;
+        terminal::enable_raw_mode()?;
+        screen.hide_cursor()?;
+        screen.clear()?;
+
+        Ok(screen)
+    }
+}
+
+impl Deref for AlternateScreen {
+    type Target = Terminal<CrosstermBackend<Stdout>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for AlternateScreen {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for AlternateScreen {
+    fn drop(&mut self) {
+        // Errors can't be reported from here, restoring as much as possible is all that's left.
+        let _ = self.0.show_cursor();
+        let _ = execute!(self.0.backend_mut(), LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Raw mode with a hidden cursor, on the main screen, which is restored once this is dropped like
+/// `AlternateScreen`.
+pub struct RawMode(());
+
+impl RawMode {
+    pub fn enter() -> io::Result<Self> {
+        let guard = RawMode(());
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), Hide)?;
+
+        Ok(guard)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// What `wait_for_key` returned for.
+pub enum Wake {
+    Key,
+    Resize,
+    Deadline,
+}
+
+/// Blocks until `deadline` is reached, returning early if a key that `is_key` accepts is
+/// pressed or the terminal is resized, so that what is on screen can be drawn again.
+pub fn wait_for_key(deadline: Instant, is_key: impl Fn(KeyEvent) -> bool) -> io::Result<Wake> {
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(Wake::Deadline);
+        }
+
+        if event::poll(deadline - now)? {
+            match event::read()? {
+                Event::Key(key) if is_key(key) => return Ok(Wake::Key),
+                Event::Resize(..) => return Ok(Wake::Resize),
+                _ => (),
+            }
+        }
+    }
+}
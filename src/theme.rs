@@ -1,28 +1,49 @@
-use serde::{Deserialize, Serialize};
+use crate::ascii;
+use crate::cli::ColorDepth;
+use crate::color;
+use crate::data::ReadoutKey;
+use crate::paths;
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
-use toml;
-use tui::style::Color;
+use tui::layout;
+use tui::style::{Color, Modifier};
+use tui::widgets::{BorderType, Borders};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 struct Randomize {
     key_color: bool,
     separator_color: bool,
+    // Maps every ANSI color of the ASCII art to a random color.
+    ascii_colors: bool,
+    // Restricts the random colors to these ones.
+    palette: Vec<ThemeColor>,
 }
 
-impl Default for Randomize {
-    fn default() -> Self {
-        Randomize {
-            key_color: false,
-            separator_color: false,
-        }
-    }
-}
+/// A color that can be used where `#[serde(with = "ColorDef")]` can't, e.g. in collections.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThemeColor(#[serde(with = "ColorDef")] pub Color);
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ASCII {
     path: Option<PathBuf>,
+    #[serde(default)]
+    builtin: Option<String>,
     #[serde(with = "ColorDef")]
     color: Color,
+    #[serde(default)]
+    image: Image,
+    #[serde(default)]
+    animation: Animation,
+    /// Replaces the ANSI colors of the art, keyed by color name, e.g. `Red = "Magenta"`.
+    #[serde(default)]
+    remap: HashMap<String, ThemeColor>,
+    #[serde(default)]
+    gradient: Option<Gradient>,
 }
 
 impl Default for ASCII {
@@ -30,6 +51,149 @@ impl Default for ASCII {
         ASCII {
             color: Color::Reset,
             path: None,
+            builtin: None,
+            image: Image::default(),
+            animation: Animation::default(),
+            remap: HashMap::new(),
+            gradient: None,
+        }
+    }
+}
+
+/// Colors the ASCII art with a gradient going through `colors`, evenly spaced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gradient {
+    #[serde(default)]
+    pub direction: GradientDirection,
+    pub colors: Vec<ThemeColor>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum GradientDirection {
+    // From the top row to the bottom row
+    #[default]
+    Vertical,
+    // From the leftmost column to the rightmost column
+    Horizontal,
+}
+
+/// Controls the playback of ASCII art made of several frames, i.e. when `custom_ascii.path`
+/// points to a directory of frames or `separator` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Animation {
+    /// The number of frames shown per second.
+    pub frame_rate: f32,
+    /// How long the animation plays for, in seconds. It plays until a key is pressed if `0`, and
+    /// shows every frame once if unset.
+    pub duration: Option<f32>,
+    /// The line that separates frames stored in a single file.
+    pub separator: Option<String>,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Animation {
+            frame_rate: 10.0,
+            duration: None,
+            separator: None,
+        }
+    }
+}
+
+/// Controls how a PNG or JPEG pointed to by `custom_ascii.path` is turned into text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Image {
+    /// The width of the resulting art, in columns.
+    pub width: u16,
+    pub style: ImageStyle,
+    pub colors: ColorDepth,
+    /// Whether to apply Floyd-Steinberg dithering when reducing the colors to a palette.
+    pub dither: bool,
+    /// The terminal graphics protocol used to show the image itself instead of text art.
+    pub protocol: GraphicsProtocol,
+}
+
+impl Default for Image {
+    fn default() -> Self {
+        Image {
+            width: 32,
+            style: ImageStyle::Blocks,
+            colors: ColorDepth::TrueColor,
+            dither: false,
+            protocol: GraphicsProtocol::Auto,
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ImageStyle {
+    // Two pixels per cell, drawn with upper half blocks
+    Blocks,
+    // One character per cell, picked by brightness
+    ASCII,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GraphicsProtocol {
+    // Picked from the environment, text art is used if none is supported
+    Auto,
+    // The kitty graphics protocol
+    Kitty,
+    // DEC Sixel graphics
+    Sixel,
+    // Always use text art
+    None,
+}
+
+/// Where the ASCII art goes relative to the readouts, and how the smaller of the two is aligned
+/// along the other one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Layout {
+    position: Position,
+    alignment: Alignment,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Position {
+    #[default]
+    #[serde(alias = "left")]
+    Left,
+    #[serde(alias = "right")]
+    Right,
+    #[serde(alias = "top")]
+    Top,
+    #[serde(alias = "bottom")]
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Alignment {
+    // Top when side by side, left when stacked
+    #[default]
+    #[serde(alias = "start")]
+    Start,
+    #[serde(alias = "center")]
+    Center,
+    // Bottom when side by side, right when stacked
+    #[serde(alias = "end")]
+    End,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Export {
+    #[serde(with = "ColorDef")]
+    background: Color,
+}
+
+impl Default for Export {
+    fn default() -> Self {
+        Export {
+            background: Color::Reset,
         }
     }
 }
@@ -44,9 +208,10 @@ pub enum Palette {
     Full,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(remote = "Color")]
-enum ColorDef {
+enum ColorVariant {
     Reset,
     Black,
     Red,
@@ -68,6 +233,39 @@ enum ColorDef {
     Indexed(u8),
 }
 
+/// (De)serializes the colors of a theme. On top of the variants of `Color`, colors may be
+/// written as strings understood by `color::parse_color`, e.g. `"#1e90ff"`.
+struct ColorDef;
+
+impl ColorDef {
+    fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        ColorVariant::serialize(color, serializer)
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        struct ColorVisitor;
+
+        impl<'de> Visitor<'de> for ColorVisitor {
+            type Value = Color;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a color name, \"#rrggbb\", \"rgb(r, g, b)\" or \"ansi:N\"")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Color, E> {
+                color::parse_color(value).map_err(E::custom)
+            }
+
+            // e.g. `{ Rgb = [30, 144, 255] }` or `{ Indexed = 208 }`
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Color, A::Error> {
+                ColorVariant::deserialize(de::value::MapAccessDeserializer::new(map))
+            }
+        }
+
+        deserializer.deserialize_any(ColorVisitor)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InnerMargin {
     x: u16,
@@ -85,6 +283,18 @@ pub struct Block {
     title: String,
     visible: bool,
     inner_margin: InnerMargin,
+    #[serde(default)]
+    border: BorderStyle,
+    #[serde(default = "Side::all")]
+    sides: Vec<Side>,
+    #[serde(default)]
+    border_color: Option<ThemeColor>,
+    #[serde(default)]
+    title_color: Option<ThemeColor>,
+    #[serde(default)]
+    title_alignment: TitleAlignment,
+    #[serde(default)]
+    around: BoxTarget,
 }
 
 impl Default for Block {
@@ -93,16 +303,88 @@ impl Default for Block {
             title: String::new(),
             visible: false,
             inner_margin: InnerMargin::default(),
+            border: BorderStyle::default(),
+            sides: Side::all(),
+            border_color: None,
+            title_color: None,
+            title_alignment: TitleAlignment::default(),
+            around: BoxTarget::default(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum BorderStyle {
+    #[serde(alias = "plain")]
+    Plain,
+    #[serde(alias = "double")]
+    Double,
+    #[serde(alias = "thick")]
+    Thick,
+    #[default]
+    #[serde(alias = "rounded")]
+    Rounded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Side {
+    #[serde(alias = "top")]
+    Top,
+    #[serde(alias = "bottom")]
+    Bottom,
+    #[serde(alias = "left")]
+    Left,
+    #[serde(alias = "right")]
+    Right,
+}
+
+impl Side {
+    fn all() -> Vec<Side> {
+        vec![Side::Top, Side::Bottom, Side::Left, Side::Right]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum TitleAlignment {
+    #[default]
+    #[serde(alias = "left")]
+    Left,
+    #[serde(alias = "center")]
+    Center,
+    #[serde(alias = "right")]
+    Right,
+}
+
+/// What the box is drawn around.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum BoxTarget {
+    #[default]
+    #[serde(alias = "readouts")]
+    Readouts,
+    #[serde(alias = "ascii")]
+    Ascii,
+    // A single box holding the ASCII art and the readouts
+    #[serde(alias = "both")]
+    Both,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Bar {
     pub glyph: String,
     pub symbol_open: char,
     pub symbol_close: char,
     pub visible: bool,
+    /// The number of segments, or cells with `BarStyle::Eighths`.
+    pub length: usize,
+    pub style: BarStyle,
+    /// Shows the percentage after the bar.
+    pub show_percentage: bool,
+    /// Colors the filled segments by where they are along the bar, from the first color to the
+    /// last, unless a threshold is reached.
+    pub gradient: Vec<ThemeColor>,
+    /// Colors the unfilled segments, which are left blank without it if `key_color` is white.
+    pub unfilled_color: Option<ThemeColor>,
 }
 
 impl Default for Bar {
@@ -112,57 +394,242 @@ impl Default for Bar {
             symbol_open: '(',
             symbol_close: ')',
             visible: false,
+            length: 10,
+            style: BarStyle::Spaced,
+            show_percentage: false,
+            gradient: Vec::new(),
+            unfilled_color: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum BarStyle {
+    // The glyphs are separated by spaces
+    #[default]
+    Spaced,
+    // The glyphs touch each other
+    Continuous,
+    // Block elements filled down to an eighth of a cell, the glyph is ignored
+    Eighths,
+}
+
+/// The keys shown next to the readouts, by the theme name of the readout, e.g.
+/// `local_ip = "IP"`. Readouts that aren't listed keep their default key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Keys(HashMap<String, String>);
+
+impl Keys {
+    pub fn get(&self, key: ReadoutKey) -> &str {
+        self.0
+            .get(key.theme_name())
+            .map_or(key.default_label(), String::as_str)
+    }
+}
+
+/// Overrides how the key and the value of one readout look, see `[styles.<readout>]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReadoutStyle {
+    pub key_color: Option<ThemeColor>,
+    // Only applies to the parts of the value that aren't already colored, e.g. not to bars.
+    pub value_color: Option<ThemeColor>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl ReadoutStyle {
+    /// Returns the modifiers that apply to both the key and the value.
+    pub fn modifiers(&self) -> Modifier {
+        let mut modifiers = Modifier::empty();
+        for (enabled, modifier) in [
+            (self.bold, Modifier::BOLD),
+            (self.italic, Modifier::ITALIC),
+            (self.underline, Modifier::UNDERLINED),
+        ] {
+            if enabled {
+                modifiers.insert(modifier);
+            }
         }
+
+        modifiers
     }
 }
 
+/// Colors a readout that reports a percentage once it reaches `warning` or `critical`. Lower
+/// values are the worse ones if `critical` is below `warning`, e.g. for the battery.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Keys {
-    pub host: String,
-    pub kernel: String,
-    pub battery: String,
-    pub os: String,
-    pub de: String,
-    pub wm: String,
-    pub distro: String,
-    pub terminal: String,
-    pub shell: String,
-    pub packages: String,
-    pub uptime: String,
-    pub memory: String,
-    pub machine: String,
-    pub local_ip: String,
-    pub backlight: String,
-    pub resolution: String,
-    pub cpu_load: String,
-    pub cpu: String,
-}
-
-impl Default for Keys {
+#[serde(default)]
+pub struct Threshold {
+    pub warning: f32,
+    pub critical: f32,
+    pub warning_color: ThemeColor,
+    pub critical_color: ThemeColor,
+}
+
+impl Default for Threshold {
     fn default() -> Self {
-        Self {
-            host: String::from("Host"),
-            kernel: String::from("Kernel"),
-            battery: String::from("Battery"),
-            os: String::from("OS"),
-            de: String::from("DE"),
-            wm: String::from("WM"),
-            distro: String::from("Distro"),
-            terminal: String::from("Terminal"),
-            shell: String::from("Shell"),
-            packages: String::from("Packages"),
-            uptime: String::from("Uptime"),
-            memory: String::from("Memory"),
-            machine: String::from("Machine"),
-            local_ip: String::from("Local IP"),
-            backlight: String::from("Brightness"),
-            resolution: String::from("Resolution"),
-            cpu_load: String::from("CPU Load"),
-            cpu: String::from("CPU"),
+        Threshold {
+            warning: 75.0,
+            critical: 90.0,
+            warning_color: ThemeColor(Color::Yellow),
+            critical_color: ThemeColor(Color::Red),
+        }
+    }
+}
+
+impl Threshold {
+    /// Returns the color of a readout at `percentage`, if it reached one of the levels.
+    pub fn color(&self, percentage: f32) -> Option<Color> {
+        let reached = |level: f32| match self.critical < self.warning {
+            true => percentage <= level,
+            false => percentage >= level,
+        };
+
+        if reached(self.critical) {
+            Some(self.critical_color.0)
+        } else if reached(self.warning) {
+            Some(self.warning_color.0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Glyphs put in front of every key, picked from a built-in set unless overridden.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Icons {
+    pub set: IconSet,
+    /// Replaces the icon of a readout, by the same names as in `[keys]`.
+    pub overrides: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum IconSet {
+    #[default]
+    #[serde(alias = "none")]
+    None,
+    // Needs a font patched by the Nerd Fonts project
+    #[serde(alias = "nerd-font")]
+    NerdFont,
+    #[serde(alias = "emoji")]
+    Emoji,
+}
+
+impl IconSet {
+    pub fn icon(&self, key: ReadoutKey) -> Option<&'static str> {
+        let icon = match self {
+            IconSet::None => return None,
+            IconSet::NerdFont => match key {
+                ReadoutKey::Host => "\u{f007}",
+                ReadoutKey::Machine => "\u{f108}",
+                ReadoutKey::Kernel => "\u{f013}",
+                ReadoutKey::Distribution => "\u{f31a}",
+                ReadoutKey::OperatingSystem => "\u{f085}",
+                ReadoutKey::DesktopEnvironment => "\u{f2d2}",
+                ReadoutKey::WindowManager => "\u{f2d0}",
+                ReadoutKey::Packages => "\u{f187}",
+                ReadoutKey::Shell => "\u{f120}",
+                ReadoutKey::Terminal => "\u{f489}",
+                ReadoutKey::LocalIP => "\u{f0ac}",
+                ReadoutKey::Backlight => "\u{f185}",
+                ReadoutKey::Resolution => "\u{f26c}",
+                ReadoutKey::Uptime => "\u{f017}",
+                ReadoutKey::Processor => "\u{f2db}",
+                ReadoutKey::ProcessorLoad => "\u{f0e4}",
+                ReadoutKey::Memory => "\u{f035b}",
+                ReadoutKey::Battery => "\u{f240}",
+            },
+            IconSet::Emoji => match key {
+                ReadoutKey::Host => "👤",
+                ReadoutKey::Machine => "💻",
+                ReadoutKey::Kernel => "🌰",
+                ReadoutKey::Distribution => "🐧",
+                ReadoutKey::OperatingSystem => "💿",
+                ReadoutKey::DesktopEnvironment => "🎨",
+                ReadoutKey::WindowManager => "🪟",
+                ReadoutKey::Packages => "📦",
+                ReadoutKey::Shell => "🐚",
+                ReadoutKey::Terminal => "📟",
+                ReadoutKey::LocalIP => "🌐",
+                ReadoutKey::Backlight => "🔆",
+                ReadoutKey::Resolution => "📐",
+                ReadoutKey::Uptime => "⏰",
+                ReadoutKey::Processor => "🧠",
+                ReadoutKey::ProcessorLoad => "📈",
+                ReadoutKey::Memory => "💾",
+                ReadoutKey::Battery => "🔋",
+            },
+        };
+
+        Some(icon)
+    }
+}
+
+/// Where the separator goes, relative to the key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SeparatorPosition {
+    // In a column of its own, after the widest key
+    #[default]
+    #[serde(alias = "column")]
+    Column,
+    // Right after the key, e.g. "Host: root"
+    #[serde(alias = "key")]
+    Key,
+}
+
+/// How the keys line up with each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum KeyAlignment {
+    #[default]
+    #[serde(alias = "left")]
+    Left,
+    #[serde(alias = "right")]
+    Right,
+    // Every key only takes up as much room as it needs, so the values don't line up either
+    #[serde(alias = "none")]
+    None,
+}
+
+/// Splits the readouts into titled sections, which are flowed into columns when there is room.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Groups {
+    // Draws a line between the sections
+    dividers: bool,
+    // The most columns the sections are flowed into
+    columns: usize,
+    sections: Vec<Group>,
+}
+
+impl Default for Groups {
+    fn default() -> Self {
+        Groups {
+            dividers: false,
+            columns: 1,
+            sections: Vec::new(),
         }
     }
 }
 
+/// A section of readouts, by their theme names, e.g. `readouts = ["host", "kernel"]`. The
+/// readouts keep the order they're shown in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Group {
+    pub title: String,
+    pub readouts: Vec<String>,
+}
+
+impl Group {
+    pub fn contains(&self, key: ReadoutKey) -> bool {
+        self.readouts.iter().any(|name| name == key.theme_name())
+    }
+}
+
 /// This structure defines the skeleton of custom themes which are deserialized from TOML files.
 /// See [https://github.com/Macchina-CLI/macchina/blob/main/theme/Carbon.toml](this) for an example
 /// theme.
@@ -170,9 +637,14 @@ impl Default for Keys {
 #[serde(default)]
 pub struct Theme {
     custom_ascii: ASCII,
+    layout: Layout,
+    export: Export,
     bar: Bar,
     r#box: Block,
     separator: String,
+    separator_position: SeparatorPosition,
+    key_alignment: KeyAlignment,
+    max_value_width: Option<usize>,
     randomize: Randomize,
     spacing: usize,
     padding: usize,
@@ -181,6 +653,10 @@ pub struct Theme {
     prefer_small_ascii: bool,
     hide_bar_delimiters: bool,
     pub keys: Keys,
+    styles: HashMap<String, ReadoutStyle>,
+    icons: Icons,
+    thresholds: HashMap<String, Threshold>,
+    groups: Groups,
     #[serde(with = "ColorDef")]
     key_color: Color,
     #[serde(with = "ColorDef")]
@@ -197,13 +673,22 @@ impl Default for Theme {
             hide_bar_delimiters: false,
             prefer_small_ascii: false,
             palette: None,
+            separator_position: SeparatorPosition::default(),
+            key_alignment: KeyAlignment::default(),
+            max_value_width: None,
             spacing: 2,
             padding: 2,
             randomize: Randomize::default(),
             custom_ascii: ASCII::default(),
+            layout: Layout::default(),
+            export: Export::default(),
             bar: Bar::default(),
             r#box: Block::default(),
             keys: Keys::default(),
+            styles: HashMap::new(),
+            icons: Icons::default(),
+            thresholds: HashMap::new(),
+            groups: Groups::default(),
         }
     }
 }
@@ -215,6 +700,9 @@ impl Theme {
             key_color: custom.key_color,
             separator: custom.separator,
             separator_color: custom.separator_color,
+            separator_position: custom.separator_position,
+            key_alignment: custom.key_alignment,
+            max_value_width: custom.max_value_width,
             spacing: custom.spacing,
             padding: custom.padding,
             palette: custom.palette,
@@ -223,8 +711,14 @@ impl Theme {
             prefer_small_ascii: custom.prefer_small_ascii,
             r#box: custom.r#box,
             custom_ascii: custom.custom_ascii,
+            layout: custom.layout,
+            export: custom.export,
             randomize: custom.randomize,
             keys: custom.keys,
+            styles: custom.styles,
+            icons: custom.icons,
+            thresholds: custom.thresholds,
+            groups: custom.groups,
         }
     }
 
@@ -240,6 +734,19 @@ impl Theme {
         &self.separator
     }
 
+    pub fn get_separator_position(&self) -> SeparatorPosition {
+        self.separator_position
+    }
+
+    pub fn get_key_alignment(&self) -> KeyAlignment {
+        self.key_alignment
+    }
+
+    /// Returns the width past which values are cut off with an ellipsis, if any.
+    pub fn get_max_value_width(&self) -> Option<usize> {
+        self.max_value_width.filter(|width| *width > 0)
+    }
+
     pub fn set_separator(&mut self, separator: impl ToString) {
         self.separator = separator.to_string()
     }
@@ -256,6 +763,36 @@ impl Theme {
         self.key_color
     }
 
+    pub fn get_readout_style(&self, key: ReadoutKey) -> Option<&ReadoutStyle> {
+        self.styles.get(key.theme_name())
+    }
+
+    /// Returns the color the `[thresholds]` of the theme give to `key` at `percentage`.
+    pub fn get_threshold_color(&self, key: ReadoutKey, percentage: f32) -> Option<Color> {
+        self.thresholds
+            .get(key.theme_name())
+            .and_then(|threshold| threshold.color(percentage))
+    }
+
+    pub fn get_groups(&self) -> &[Group] {
+        &self.groups.sections
+    }
+
+    pub fn has_group_dividers(&self) -> bool {
+        self.groups.dividers
+    }
+
+    pub fn get_max_columns(&self) -> usize {
+        self.groups.columns.max(1)
+    }
+
+    pub fn get_icon(&self, key: ReadoutKey) -> Option<&str> {
+        match self.icons.overrides.get(key.theme_name()) {
+            Some(icon) => Some(icon.as_str()).filter(|icon| !icon.is_empty()),
+            None => self.icons.set.icon(key),
+        }
+    }
+
     pub fn set_key_color(&mut self, color: Color) {
         self.key_color = color
     }
@@ -268,6 +805,47 @@ impl Theme {
         self.r#box.visible
     }
 
+    pub fn get_box_target(&self) -> BoxTarget {
+        self.r#box.around
+    }
+
+    pub fn get_box_border_type(&self) -> BorderType {
+        match self.r#box.border {
+            BorderStyle::Plain => BorderType::Plain,
+            BorderStyle::Double => BorderType::Double,
+            BorderStyle::Thick => BorderType::Thick,
+            BorderStyle::Rounded => BorderType::Rounded,
+        }
+    }
+
+    pub fn get_box_borders(&self) -> Borders {
+        self.r#box
+            .sides
+            .iter()
+            .fold(Borders::NONE, |borders, side| match side {
+                Side::Top => borders | Borders::TOP,
+                Side::Bottom => borders | Borders::BOTTOM,
+                Side::Left => borders | Borders::LEFT,
+                Side::Right => borders | Borders::RIGHT,
+            })
+    }
+
+    pub fn get_box_border_color(&self) -> Option<Color> {
+        self.r#box.border_color.map(|c| c.0)
+    }
+
+    pub fn get_box_title_color(&self) -> Option<Color> {
+        self.r#box.title_color.map(|c| c.0)
+    }
+
+    pub fn get_box_title_alignment(&self) -> layout::Alignment {
+        match self.r#box.title_alignment {
+            TitleAlignment::Left => layout::Alignment::Left,
+            TitleAlignment::Center => layout::Alignment::Center,
+            TitleAlignment::Right => layout::Alignment::Right,
+        }
+    }
+
     pub fn is_key_color_randomized(&self) -> bool {
         self.randomize.key_color
     }
@@ -276,6 +854,36 @@ impl Theme {
         self.randomize.separator_color
     }
 
+    pub fn are_ascii_colors_randomized(&self) -> bool {
+        self.randomize.ascii_colors
+    }
+
+    /// Returns the colors random ones are picked from, or `None` if any color may be picked.
+    pub fn get_random_palette(&self) -> Option<Vec<Color>> {
+        match self.randomize.palette.is_empty() {
+            true => None,
+            false => Some(self.randomize.palette.iter().map(|c| c.0).collect()),
+        }
+    }
+
+    /// Returns the colors the ANSI colors of the ASCII art are replaced with, keyed by name.
+    pub fn get_ascii_remap(&self) -> &HashMap<String, ThemeColor> {
+        &self.custom_ascii.remap
+    }
+
+    pub fn set_ascii_remap(&mut self, name: &str, color: Color) {
+        self.custom_ascii
+            .remap
+            .insert(name.to_owned(), ThemeColor(color));
+    }
+
+    pub fn get_ascii_gradient(&self) -> Option<&Gradient> {
+        self.custom_ascii
+            .gradient
+            .as_ref()
+            .filter(|g| !g.colors.is_empty())
+    }
+
     pub fn get_horizontal_margin(&self) -> u16 {
         self.r#box.inner_margin.x
     }
@@ -296,6 +904,18 @@ impl Theme {
         self.custom_ascii.color
     }
 
+    pub fn get_layout_position(&self) -> Position {
+        self.layout.position
+    }
+
+    pub fn get_layout_alignment(&self) -> Alignment {
+        self.layout.alignment
+    }
+
+    pub fn get_export_background(&self) -> Color {
+        self.export.background
+    }
+
     pub fn get_palette_type(&self) -> Option<&Palette> {
         self.palette.as_ref()
     }
@@ -312,6 +932,27 @@ impl Theme {
         self.custom_ascii.path.as_ref()
     }
 
+    pub fn get_builtin_ascii(&self) -> Option<&str> {
+        self.custom_ascii.builtin.as_deref()
+    }
+
+    pub fn set_builtin_ascii(&mut self, id: &str) {
+        self.custom_ascii.builtin = Some(id.to_owned());
+        self.custom_ascii.path = None;
+    }
+
+    pub fn set_custom_ascii_path(&mut self, path: PathBuf) {
+        self.custom_ascii.path = Some(path);
+    }
+
+    pub fn get_custom_ascii_animation(&self) -> &Animation {
+        &self.custom_ascii.animation
+    }
+
+    pub fn get_custom_ascii_image(&self) -> &Image {
+        &self.custom_ascii.image
+    }
+
     pub fn is_using_bars(&self) -> bool {
         self.bar.visible
     }
@@ -341,41 +982,155 @@ impl Theme {
         self.bar.symbol_close = '\0';
     }
 
-    /// Searches for and returns a theme from `~/.config/macchina/themes`
-    pub fn get_theme(name: &str, dir: Option<PathBuf>) -> Result<Self, std::io::Error> {
-        use std::io::Read;
-        let mut theme_path = std::path::PathBuf::new();
-        theme_path.push(dir.ok_or_else(|| {
+    /// Reads the theme called `name` from the first directory that has it, see
+    /// `paths::data_dirs`.
+    pub fn get_theme(name: &str) -> Result<Self, std::io::Error> {
+        let table = Theme::read_theme_table(name)?;
+        let table =
+            Theme::resolve_inherits(table, &mut vec![name.to_string()], &Theme::read_theme_table)?;
+
+        let theme: Theme = toml::Value::Table(table).try_into().map_err(|e| {
             std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "$XDG_CONFIG_HOME was not found; fallback $HOME/.config also failed.",
+                std::io::ErrorKind::InvalidData,
+                format!("Could not parse theme: {}", e),
             )
-        })?);
+        })?;
+        theme.validate()?;
 
-        theme_path.push(std::path::Path::new(&format!(
-            "macchina/themes/{}.toml",
-            name
-        )));
+        Ok(theme)
+    }
 
-        let mut buffer: Vec<u8> = Vec::new();
-        let mut theme = std::fs::File::open(theme_path)?;
-        theme.read_to_end(&mut buffer)?;
+    /// Checks the names the theme uses as table keys, which serde takes as they come.
+    fn validate(&self) -> Result<(), std::io::Error> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
 
-        toml::from_slice(&buffer).map_err(|_| {
-            std::io::Error::new(std::io::ErrorKind::InvalidData, "Could not parse theme.")
+        for name in self.custom_ascii.remap.keys() {
+            if !ascii::ANSI_COLOR_NAMES
+                .iter()
+                .any(|(color, _)| color.eq_ignore_ascii_case(name))
+            {
+                return Err(invalid(format!(
+                    "\"{}\" in [custom_ascii.remap] is not one of the 16 ANSI color names.",
+                    name
+                )));
+            }
+        }
+
+        check_readout_names("keys", self.keys.0.keys())?;
+        check_readout_names("styles", self.styles.keys())?;
+        check_readout_names("icons.overrides", self.icons.overrides.keys())?;
+        check_readout_names("thresholds", self.thresholds.keys())?;
+        let mut grouped: Vec<&String> = Vec::new();
+        for group in &self.groups.sections {
+            check_readout_names("groups.sections", group.readouts.iter())?;
+            for name in &group.readouts {
+                if grouped.contains(&name) {
+                    return Err(invalid(format!(
+                        "\"{}\" is in more than one of [groups.sections], a readout is only \
+                         shown once.",
+                        name
+                    )));
+                }
+                grouped.push(name);
+            }
+        }
+        for name in self.thresholds.keys() {
+            match ReadoutKey::from_theme_name(name) {
+                Some(ReadoutKey::Memory)
+                | Some(ReadoutKey::Battery)
+                | Some(ReadoutKey::ProcessorLoad)
+                | Some(ReadoutKey::Backlight) => (),
+                _ => {
+                    return Err(invalid(format!(
+                        "\"{}\" in [thresholds] doesn't report a percentage, only memory, \
+                         battery, cpu_load and backlight do.",
+                        name
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the theme called `name` from `<dir>/macchina/themes`, without deserializing it.
+    fn read_theme_table(name: &str) -> Result<toml::value::Table, std::io::Error> {
+        let theme_path = paths::find_file("themes", name, "toml").ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("There is no theme called \"{}\".", name),
+            )
+        })?;
+        let buffer = std::fs::read(theme_path)?;
+
+        toml::from_slice(&buffer).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Could not parse theme \"{}\": {}", name, e),
+            )
         })
     }
 
+    /// Merges `table` over the themes it inherits from, which are read with `read_table`.
+    /// `chain` holds the names of the themes that led to `table`, itself included.
+    fn resolve_inherits(
+        mut table: toml::value::Table,
+        chain: &mut Vec<String>,
+        read_table: &impl Fn(&str) -> Result<toml::value::Table, std::io::Error>,
+    ) -> Result<toml::value::Table, std::io::Error> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let child = chain.last().cloned().unwrap_or_default();
+
+        let parent = match table.remove("inherits") {
+            None => return Ok(table),
+            Some(toml::Value::String(parent)) => parent,
+            Some(_) => {
+                return Err(invalid(format!(
+                    "\"inherits\" must be the name of a theme, in theme \"{}\".",
+                    child
+                )))
+            }
+        };
+
+        if chain.contains(&parent) {
+            chain.push(parent);
+            return Err(invalid(format!(
+                "Themes inherit from each other: {}",
+                chain.join(" -> ")
+            )));
+        }
+
+        // The parent is looked up like any other theme.
+        let parent_table = match read_table(&parent) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(invalid(format!(
+                "Theme \"{}\" inherits from \"{}\", which doesn't exist.",
+                child, parent
+            ))),
+            result => result,
+        }?;
+
+        chain.push(parent);
+        let mut merged = Theme::resolve_inherits(parent_table, chain, read_table)?;
+        merge_tables(&mut merged, table);
+
+        Ok(merged)
+    }
+
     fn _print_theme_test() {
         let cust = Theme {
             bar: Bar::default(),
             separator: String::from("<-->"),
+            separator_position: SeparatorPosition::default(),
+            key_alignment: KeyAlignment::default(),
+            max_value_width: None,
             spacing: 2,
             padding: 0,
             r#box: Block {
                 title: String::from("Neon"),
                 visible: true,
-                inner_margin: InnerMargin::default(),
+                ..Block::default()
             },
             randomize: Randomize::default(),
             hide_ascii: false,
@@ -384,10 +1139,202 @@ impl Theme {
             palette: Some(Palette::Full),
             key_color: Color::Rgb(10, 33, 51),
             custom_ascii: ASCII::default(),
+            layout: Layout::default(),
+            export: Export::default(),
             separator_color: Color::Indexed(100),
             keys: Keys::default(),
+            styles: HashMap::new(),
+            icons: Icons::default(),
+            thresholds: HashMap::new(),
+            groups: Groups::default(),
         };
 
         println!("{}", toml::to_string_pretty(&cust).unwrap());
     }
 }
+
+/// Fails with an error naming the first of `names` that isn't the theme name of a readout, see
+/// `ReadoutKey::theme_name`, where `table` is the table they were found in.
+fn check_readout_names<'n>(
+    table: &str,
+    mut names: impl Iterator<Item = &'n String>,
+) -> Result<(), std::io::Error> {
+    match names.find(|name| ReadoutKey::from_theme_name(name).is_none()) {
+        None => Ok(()),
+        Some(name) => {
+            let known: Vec<&str> = ReadoutKey::variants()
+                .iter()
+                .filter_map(|variant| variant.parse().ok())
+                .map(|key: ReadoutKey| key.theme_name())
+                .collect();
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "\"{}\" in [{}] is not the name of a readout, expected one of: {}.",
+                    name,
+                    table,
+                    known.join(", ")
+                ),
+            ))
+        }
+    }
+}
+
+/// Copies the values of `overrides` into `base`, merging the tables they both have.
+fn merge_tables(base: &mut toml::value::Table, overrides: toml::value::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => {
+                merge_tables(base, value)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(toml: &str) -> Result<(), String> {
+        let theme: Theme = toml::from_str(toml).unwrap();
+        theme.validate().map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn accepts_known_names() {
+        let theme = r#"
+            [keys]
+            cpu_load = "Load"
+
+            [thresholds.memory]
+            warning = 80
+
+            [[groups.sections]]
+            readouts = ["host", "kernel"]
+
+            [[groups.sections]]
+            readouts = ["memory"]
+            "#;
+        assert_eq!(validate(theme), Ok(()));
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        let error = validate("[styles.hots]\nbold = true").unwrap_err();
+        assert!(error.starts_with("\"hots\" in [styles]"), "{}", error);
+
+        let error = validate("[[groups.sections]]\nreadouts = [\"cpuload\"]").unwrap_err();
+        assert!(
+            error.starts_with("\"cpuload\" in [groups.sections]"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn rejects_readouts_in_several_groups() {
+        let theme = r#"
+            [[groups.sections]]
+            readouts = ["host", "kernel"]
+
+            [[groups.sections]]
+            readouts = ["kernel"]
+            "#;
+        let error = validate(theme).unwrap_err();
+        assert!(
+            error.starts_with("\"kernel\" is in more than one"),
+            "{}",
+            error
+        );
+    }
+
+    fn table(toml: &str) -> toml::value::Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    /// Resolves the theme called `name` among `themes`, which are given as names and TOML.
+    fn resolve(themes: &[(&str, &str)], name: &str) -> Result<toml::value::Table, String> {
+        let read_table = |name: &str| {
+            themes
+                .iter()
+                .find(|(theme, _)| *theme == name)
+                .map(|(_, toml)| table(toml))
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))
+        };
+
+        let theme = read_table(name).unwrap();
+        Theme::resolve_inherits(theme, &mut vec![name.to_string()], &read_table)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn merges_nested_tables() {
+        let mut base = table(
+            "[box]
+title = \"Base\"
+[box.inner_margin]
+x = 1
+y = 1",
+        );
+        merge_tables(
+            &mut base,
+            table(
+                "[box.inner_margin]
+y = 2",
+            ),
+        );
+
+        let expected = table(
+            "[box]
+title = \"Base\"
+[box.inner_margin]
+x = 1
+y = 2",
+        );
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn child_overrides_parent() {
+        let themes = [
+            (
+                "Parent",
+                "separator = \"->\"
+key_color = \"Blue\"",
+            ),
+            (
+                "Child",
+                "inherits = \"Parent\"
+key_color = \"Red\"",
+            ),
+        ];
+
+        let expected = table(
+            "separator = \"->\"
+key_color = \"Red\"",
+        );
+        assert_eq!(resolve(&themes, "Child"), Ok(expected));
+    }
+
+    #[test]
+    fn rejects_missing_parent() {
+        let themes = [("Child", "inherits = \"Nope\"")];
+
+        let error = resolve(&themes, "Child").unwrap_err();
+        assert_eq!(
+            error,
+            "Theme \"Child\" inherits from \"Nope\", which doesn't exist."
+        );
+    }
+
+    #[test]
+    fn rejects_inheritance_cycles() {
+        let themes = [("A", "inherits = \"B\""), ("B", "inherits = \"A\"")];
+
+        let error = resolve(&themes, "A").unwrap_err();
+        assert_eq!(error, "Themes inherit from each other: A -> B -> A");
+    }
+}
@@ -0,0 +1,65 @@
+use crate::cli::Opt;
+use crate::color;
+use crate::data::{self, Readout};
+use crate::fetch;
+use crate::screen::{self, AlternateScreen, Wake};
+use crate::theme::Theme;
+use crate::widgets::canvas::Canvas;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
+use tui::backend::CrosstermBackend;
+use tui::Terminal;
+
+/// Returns `true` if the key event should end the watch session, i.e. `q`, `Esc` or `Ctrl-C`.
+fn is_exit_key(key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    opt: &Opt,
+    theme: &Theme,
+    mut readout_data: Vec<Readout>,
+    interval: Duration,
+) -> io::Result<()> {
+    let color_depth = opt.color_depth.unwrap_or_else(color::detect_color_depth);
+
+    let mut deadline = Instant::now() + interval;
+    loop {
+        let width = terminal.size()?.width;
+        let (mut canvas, _) = fetch::draw_fetch(readout_data.clone(), theme, Some(width), None)?;
+        color::quantize_buffer(&mut canvas, color_depth);
+
+        terminal.draw(|f| {
+            let area = f.size();
+            f.render_widget(Canvas(&canvas), area);
+        })?;
+
+        match screen::wait_for_key(deadline, is_exit_key)? {
+            Wake::Key => return Ok(()),
+            // The fetch is drawn again at the new size, without waiting for the next interval.
+            Wake::Resize => (),
+            Wake::Deadline => {
+                deadline = Instant::now() + interval;
+                data::refresh_readouts(opt, theme, &mut readout_data);
+            }
+        }
+    }
+}
+
+/// Keeps the fetch on an alternate screen, re-collecting volatile readouts every `interval`
+/// until `q` or `Ctrl-C` is pressed.
+pub(crate) fn watch(
+    opt: &Opt,
+    theme: &Theme,
+    readout_data: Vec<Readout>,
+    interval: Duration,
+) -> io::Result<()> {
+    let mut screen = AlternateScreen::enter()?;
+    run(&mut screen, opt, theme, readout_data, interval)
+}
@@ -0,0 +1,20 @@
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::widgets::Widget;
+
+/// Copies the visible portion of an off-screen buffer onto another buffer, this is used to
+/// display a fully drawn fetch inside of a `tui::Terminal` frame.
+pub struct Canvas<'a>(pub &'a Buffer);
+
+impl<'a> Widget for Canvas<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let width = area.width.min(self.0.area.width);
+        let height = area.height.min(self.0.area.height);
+
+        for y in 0..height {
+            for x in 0..width {
+                *buf.get_mut(area.x + x, area.y + y) = self.0.get(x, y).clone();
+            }
+        }
+    }
+}
@@ -1 +1,2 @@
+pub mod canvas;
 pub mod readout;
@@ -1,12 +1,69 @@
 use crate::data::{Readout, ReadoutKey};
-use crate::theme::Palette;
 use crate::theme::Theme;
+use crate::theme::{KeyAlignment, Palette, SeparatorPosition};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::str::FromStr;
 use tui::buffer::Buffer;
 use tui::layout::{Margin, Rect};
-use tui::style::{Color, Style};
+use tui::style::{Color, Modifier, Style};
 use tui::text::{Span, Spans, Text};
 use tui::widgets::{Block, Paragraph, Widget};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Cuts every line of `text` that is wider than `width` short, ending it with an ellipsis in the
+/// style of the span that was cut.
+fn truncate(mut text: Text, width: usize) -> Text {
+    for line in text.lines.iter_mut().filter(|line| line.width() > width) {
+        let mut room = width.saturating_sub(1);
+        let mut spans = Vec::new();
+        let mut style = Style::default();
+
+        for span in &line.0 {
+            style = span.style;
+            let content: String = span
+                .content
+                .chars()
+                .take_while(|c| match room.checked_sub(c.width().unwrap_or(0)) {
+                    Some(left) => {
+                        room = left;
+                        true
+                    }
+                    None => false,
+                })
+                .collect();
+
+            let cut = content.len() < span.content.len();
+            spans.push(Span::styled(content, style));
+            if cut {
+                break;
+            }
+        }
+
+        spans.push(Span::styled("…", style));
+        *line = Spans::from(spans);
+    }
+
+    text
+}
+
+/// The columns between two columns of readouts.
+const COLUMN_GAP: u16 = 4;
+
+/// Returns the room that `block` and `margin` take up around what they hold: `x` and `y` are
+/// the left and top insets, `width` and `height` the extra columns and rows in total.
+pub fn block_insets(block: &Block, margin: &Margin) -> Rect {
+    // `Block` doesn't tell which borders it has, but the smallest area with room for all of
+    // them shows it: every border or title it draws takes a column or row away.
+    let outer = Rect::new(0, 0, 2, 2);
+    let inner = block.inner(outer);
+    Rect::new(
+        inner.x + margin.horizontal,
+        inner.y + margin.vertical,
+        outer.width - inner.width + 2 * margin.horizontal,
+        outer.height - inner.height + 2 * margin.vertical,
+    )
+}
 
 pub struct ReadoutList<'a> {
     block: Option<Block<'a>>,
@@ -14,9 +71,28 @@ pub struct ReadoutList<'a> {
     items: Vec<Readout<'a>>,
     theme: &'a Theme,
     block_inner_margin: Margin,
+    columns: usize,
+}
+
+/// Readouts that are drawn together, under a title if they make up a group of the theme.
+struct Section<'s, 'a> {
+    title: Option<&'s str>,
+    items: Vec<&'s Readout<'a>>,
+}
+
+impl Section<'_, '_> {
+    fn height(&self) -> u16 {
+        let title_height = u16::from(self.title.is_some());
+        self.items
+            .iter()
+            .filter_map(|item| item.1.as_ref().ok())
+            .map(|data| data.height() as u16)
+            .sum::<u16>()
+            + title_height
+    }
 }
 
-impl<'a, 'b> ReadoutList<'a> {
+impl<'a> ReadoutList<'a> {
     pub fn new<T>(items: T, theme: &'a Theme) -> ReadoutList<'a>
     where
         T: Into<Vec<Readout<'a>>>,
@@ -30,6 +106,7 @@ impl<'a, 'b> ReadoutList<'a> {
                 horizontal: 0,
                 vertical: 0,
             },
+            columns: 1,
         }
     }
 
@@ -57,6 +134,12 @@ impl<'a, 'b> ReadoutList<'a> {
         self.block_inner_margin = margin;
         self
     }
+
+    /// Sets the most columns that the sections of the list are flowed into.
+    pub fn columns(mut self, columns: usize) -> ReadoutList<'a> {
+        self.columns = columns;
+        self
+    }
 }
 
 impl<'a> Widget for ReadoutList<'a> {
@@ -78,66 +161,326 @@ impl<'a> Widget for ReadoutList<'a> {
             return;
         }
 
-        let mut height = 0;
         let keys = self.keys_to_text(&self.theme.get_key_color());
         let max_key_width = Self::get_max_key_width(&keys);
         let themed_separator = Self::get_themed_separator(
-            &self.theme.get_separator(),
+            self.theme.get_separator(),
             &self.theme.get_separator_color(),
         );
 
-        let mut max_line_width: u16 = 0;
-
-        for item in self.items.iter().filter(|f| f.1.is_ok()) {
-            //it's ok to unwrap, because we filtered out everything that is not a valid Option<T>.
-            let readout_data = item.1.as_ref().unwrap();
-            let readout_key = keys.get(&item.0).unwrap();
+        let mut height = 0;
+        let mut x = list_area.x;
+        let columns = self.flow_columns();
+        for (i, column) in columns.iter().enumerate() {
+            if x >= list_area.right() {
+                break;
+            }
 
-            let list_item_area = Rect {
-                x: list_area.x,
-                y: list_area.y + height,
-                width: list_area.width,
-                height: readout_data.height() as u16,
+            // The last column may use the rest of the line, the others are cut off at their
+            // width.
+            let (column_width, _) =
+                self.column_size(column, &keys, max_key_width, &themed_separator);
+            let available = list_area.right() - x;
+            let width = if i + 1 == columns.len() {
+                available
+            } else {
+                column_width.min(available)
             };
 
-            let constraints =
-                self.create_item_constraints(max_key_width, &themed_separator, readout_data);
-            let layout = Self::create_layout(&list_item_area, &constraints);
-
-            let total_line_width = constraints.iter().sum::<u16>() + constraints.len() as u16 - 1;
-            if total_line_width > max_line_width {
-                max_line_width = total_line_width;
-            }
-
-            let mut layout_iter = layout.iter();
-            if self.theme.get_padding() > 0 {
-                layout_iter.next();
-            }
+            let column_area = Rect::new(x, list_area.y, width, list_area.height);
+            let column_height = self.render_column(
+                buf,
+                column_area,
+                column,
+                &keys,
+                max_key_width,
+                &themed_separator,
+            );
 
-            Paragraph::new(readout_key.clone()).render(*layout_iter.next().unwrap(), buf);
-            Paragraph::new(themed_separator.clone()).render(*layout_iter.next().unwrap(), buf);
-            layout_iter.next();
-            Paragraph::new(readout_data.to_owned()).render(*layout_iter.next().unwrap(), buf);
-            height += readout_data.height() as u16;
+            height = height.max(column_height);
+            x = x.saturating_add(column_width + COLUMN_GAP);
         }
 
         if let Some(palette) = self.theme.get_palette_type() {
             self.print_palette(buf, &list_area, &mut height, palette);
         }
 
+        let (content_width, content_height) = self.content_size();
         Self::render_block(
             self.block,
             buf,
-            area.x,
-            area.y,
-            height,
-            max_line_width,
+            area,
+            content_height,
+            content_width,
             &self.block_inner_margin,
         );
     }
 }
 
 impl<'a> ReadoutList<'a> {
+    /// Returns the width and height that are needed to draw the list, including its block,
+    /// without anything being truncated.
+    pub fn size(&self) -> (u16, u16) {
+        let (mut width, mut height) = self.content_size();
+
+        if let Some(block) = &self.block {
+            let insets = block_insets(block, &self.block_inner_margin);
+            width += insets.width;
+            height += insets.height;
+        }
+
+        (width, height)
+    }
+
+    /// Returns the size of everything inside the block.
+    fn content_size(&self) -> (u16, u16) {
+        let keys = self.keys_to_text(&self.theme.get_key_color());
+        let max_key_width = Self::get_max_key_width(&keys);
+        let themed_separator = Self::get_themed_separator(
+            self.theme.get_separator(),
+            &self.theme.get_separator_color(),
+        );
+
+        let columns = self.flow_columns();
+        let (mut width, mut height) = columns
+            .iter()
+            .map(|column| self.column_size(column, &keys, max_key_width, &themed_separator))
+            .fold((0, 0), |(width, height), (column_width, column_height)| {
+                (width + column_width, height.max(column_height))
+            });
+        width += COLUMN_GAP * (columns.len() as u16).saturating_sub(1);
+
+        if let Some(palette) = self.theme.get_palette_type() {
+            // Each color of the palette is three cells wide, and there are eight per row.
+            width = width.max(self.theme.get_padding() as u16 + 24);
+            height += match palette {
+                Palette::Full => 3,
+                _ => 2,
+            };
+        }
+
+        (width, height)
+    }
+
+    /// Splits the readouts into the groups of the theme, followed by the readouts that aren't
+    /// part of any group.
+    fn sections(&self) -> Vec<Section<'_, 'a>> {
+        let items: Vec<&Readout<'a>> = self.items.iter().filter(|f| f.1.is_ok()).collect();
+        let groups = self.theme.get_groups();
+
+        let mut sections: Vec<Section> = groups
+            .iter()
+            .map(|group| Section {
+                title: Some(group.title.as_str()).filter(|title| !title.is_empty()),
+                items: items
+                    .iter()
+                    .filter(|item| group.contains(item.0))
+                    .copied()
+                    .collect(),
+            })
+            .filter(|section| !section.items.is_empty())
+            .collect();
+
+        let ungrouped: Vec<&Readout<'a>> = items
+            .into_iter()
+            .filter(|item| !groups.iter().any(|group| group.contains(item.0)))
+            .collect();
+        if !ungrouped.is_empty() {
+            sections.push(Section {
+                title: None,
+                items: ungrouped,
+            });
+        }
+
+        sections
+    }
+
+    /// Distributes the sections over as many columns as the list is allowed, in order and
+    /// keeping the columns about as tall as each other.
+    fn flow_columns(&self) -> Vec<Vec<Section<'_, 'a>>> {
+        let sections = self.sections();
+        let total_height: u16 = sections.iter().map(|s| s.height() + 1).sum();
+        let target_height = total_height.div_ceil(self.columns.max(1) as u16);
+
+        let mut columns: Vec<Vec<Section>> = vec![Vec::new()];
+        let mut height = 0;
+        for section in sections {
+            let section_height = section.height();
+            if height > 0 && height + section_height > target_height && columns.len() < self.columns
+            {
+                columns.push(Vec::new());
+                height = 0;
+            }
+
+            height += section_height + 1;
+            columns.last_mut().unwrap().push(section);
+        }
+
+        columns
+    }
+
+    fn column_size(
+        &self,
+        column: &[Section],
+        keys: &HashMap<ReadoutKey, Text>,
+        max_key_width: usize,
+        themed_separator: &Text,
+    ) -> (u16, u16) {
+        let padding = self.theme.get_padding() as u16;
+        let mut width = 0;
+        let mut height = 0;
+        for section in column {
+            if let Some(title) = section.title {
+                width = width.max(padding + title.width() as u16);
+            }
+
+            for item in &section.items {
+                //it's ok to unwrap, because sections only hold valid readouts.
+                let readout_data = item.1.as_ref().unwrap();
+                let constraints = self.create_item_constraints(
+                    max_key_width,
+                    &keys[&item.0],
+                    themed_separator,
+                    readout_data,
+                );
+                let line_width = constraints.iter().sum::<u16>() + constraints.len() as u16 - 1;
+                width = width.max(line_width);
+            }
+
+            height += section.height();
+        }
+
+        // The sections are one row away from each other.
+        height += (column.len() as u16).saturating_sub(1);
+
+        (width, height)
+    }
+
+    /// Draws the sections of a column one below the other, and returns the height they took.
+    fn render_column(
+        &self,
+        buf: &mut Buffer,
+        area: Rect,
+        column: &[Section],
+        keys: &HashMap<ReadoutKey, Text>,
+        max_key_width: usize,
+        themed_separator: &Text,
+    ) -> u16 {
+        let (column_width, _) = self.column_size(column, keys, max_key_width, themed_separator);
+        let padding = self.theme.get_padding() as u16;
+
+        // Anything that doesn't fit in the buffer gets truncated.
+        let bounds = Rect {
+            x: area.x,
+            y: buf.area.y,
+            width: area.width,
+            height: buf.area.height,
+        };
+
+        let mut height = 0;
+        for (i, section) in column.iter().enumerate() {
+            // The sections are separated by a divider, or an empty row.
+            let divider = Rect::new(area.x, area.y + height, column_width, 1);
+            if i > 0 && self.theme.has_group_dividers() {
+                if let Some(area) = Self::clip(divider, bounds) {
+                    let line = Span::styled(
+                        "─".repeat(column_width as usize),
+                        Style::default().fg(self.theme.get_separator_color()),
+                    );
+                    Paragraph::new(Spans::from(line)).render(area, buf);
+                }
+            }
+            if i > 0 {
+                height += 1;
+            }
+
+            if let Some(title) = section.title {
+                let title_area = Rect::new(area.x + padding, area.y + height, column_width, 1);
+                if let Some(area) = Self::clip(title_area, bounds) {
+                    let style = Style::default()
+                        .fg(self.theme.get_key_color())
+                        .add_modifier(Modifier::BOLD);
+                    Paragraph::new(Span::styled(title, style)).render(area, buf);
+                }
+                height += 1;
+            }
+
+            for item in &section.items {
+                //it's ok to unwrap, because sections only hold valid readouts.
+                let readout_data = item.1.as_ref().unwrap();
+                let readout_key = keys.get(&item.0).unwrap();
+
+                let list_item_area = Rect {
+                    x: area.x,
+                    y: area.y + height,
+                    width: area.width,
+                    height: readout_data.height() as u16,
+                };
+
+                let constraints = self.create_item_constraints(
+                    max_key_width,
+                    readout_key,
+                    themed_separator,
+                    readout_data,
+                );
+                let mut layout = Self::create_layout(&list_item_area, &constraints);
+
+                let key_index = usize::from(self.theme.get_padding() > 0);
+                if self.theme.get_key_alignment() == KeyAlignment::Right {
+                    let offset = max_key_width.saturating_sub(readout_key.width()) as u16;
+                    let key_area = &mut layout[key_index];
+                    key_area.x += offset;
+                    key_area.width = key_area.width.saturating_sub(offset);
+                }
+
+                let mut layout_iter = layout
+                    .iter()
+                    .skip(key_index)
+                    .map(|rect| Self::clip(*rect, bounds));
+
+                let hugs_key = self.theme.get_separator_position() == SeparatorPosition::Key;
+                if let Some(area) = layout_iter.next().unwrap() {
+                    let mut key = readout_key.clone();
+                    // A threshold that is reached colors the whole line, the key included.
+                    if let Some(color) = item
+                        .2
+                        .and_then(|percentage| self.theme.get_threshold_color(item.0, percentage))
+                    {
+                        key.patch_style(Style::default().fg(color));
+                    }
+                    if hugs_key {
+                        let separator = themed_separator.lines.iter().flat_map(|l| l.0.clone());
+                        if let Some(line) = key.lines.first_mut() {
+                            line.0.extend(separator);
+                        }
+                    }
+                    Paragraph::new(key).render(area, buf);
+                }
+                if let Some(area) = layout_iter.next().unwrap().filter(|_| !hugs_key) {
+                    Paragraph::new(themed_separator.clone()).render(area, buf);
+                }
+                layout_iter.next();
+                // Values that don't fit are cut off with an ellipsis rather than mid-glyph.
+                if let Some(area) = layout_iter.next().unwrap() {
+                    let value = truncate(self.value_text(item.0, readout_data), area.width.into());
+                    Paragraph::new(value).render(area, buf);
+                }
+                height += readout_data.height() as u16;
+            }
+        }
+
+        height
+    }
+
+    /// Returns the part of `rect` that lies within `bounds`, if any.
+    fn clip(rect: Rect, bounds: Rect) -> Option<Rect> {
+        if rect.intersects(bounds) {
+            Some(rect.intersection(bounds))
+        } else {
+            None
+        }
+    }
+
     fn print_palette(
         &self,
         buf: &mut Buffer,
@@ -188,136 +531,110 @@ impl<'a> ReadoutList<'a> {
         let area = Rect::new(
             list_area.x + padding,
             list_area.y + *height + 1,
-            list_area.width - padding,
+            list_area.width.saturating_sub(padding),
             spans.len() as u16,
         );
 
-        Paragraph::new(spans).render(area, buf);
+        if let Some(visible_area) = Self::clip(area, buf.area) {
+            Paragraph::new(spans).render(visible_area, buf);
+        }
 
         *height += area.height + 1;
     }
 
-    fn keys_to_text(&self, key_color: &Color) -> HashMap<ReadoutKey, Text> {
-        let color_style = Style::default().fg(*key_color);
-
-        let mut keys = HashMap::new();
-
-        keys.insert(
-            ReadoutKey::Host,
-            Text::styled(&self.theme.keys.host, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Machine,
-            Text::styled(&self.theme.keys.machine, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Kernel,
-            Text::styled(&self.theme.keys.kernel, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Distribution,
-            Text::styled(&self.theme.keys.distro, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::OperatingSystem,
-            Text::styled(&self.theme.keys.os, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::DesktopEnvironment,
-            Text::styled(&self.theme.keys.de, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::WindowManager,
-            Text::styled(&self.theme.keys.wm, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Packages,
-            Text::styled(&self.theme.keys.packages, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Shell,
-            Text::styled(&self.theme.keys.shell, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Terminal,
-            Text::styled(&self.theme.keys.terminal, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::LocalIP,
-            Text::styled(&self.theme.keys.local_ip, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Battery,
-            Text::styled(&self.theme.keys.battery, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Backlight,
-            Text::styled(&self.theme.keys.backlight, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Resolution,
-            Text::styled(&self.theme.keys.resolution, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Memory,
-            Text::styled(&self.theme.keys.memory, color_style),
-        );
-
-        keys.insert(
-            ReadoutKey::Processor,
-            Text::styled(&self.theme.keys.cpu, color_style),
-        );
+    fn keys_to_text(&self, key_color: &Color) -> HashMap<ReadoutKey, Text<'_>> {
+        let keys: Vec<ReadoutKey> = ReadoutKey::variants()
+            .iter()
+            .filter_map(|key| ReadoutKey::from_str(key).ok())
+            .collect();
+
+        // Icons are padded to the widest one, so that the keys still line up.
+        let icon_width = keys
+            .iter()
+            .filter_map(|key| self.theme.get_icon(*key))
+            .map(UnicodeWidthStr::width)
+            .max();
+
+        keys.into_iter()
+            .map(|key| {
+                let mut style = Style::default().fg(*key_color);
+                if let Some(readout_style) = self.theme.get_readout_style(key) {
+                    if let Some(color) = &readout_style.key_color {
+                        style = style.fg(color.0);
+                    }
+                    style = style.add_modifier(readout_style.modifiers());
+                }
+
+                let label = self.theme.keys.get(key);
+                let text = match icon_width {
+                    Some(icon_width) => {
+                        let icon = self.theme.get_icon(key).unwrap_or_default();
+                        let padding = " ".repeat(icon_width - icon.width());
+                        Cow::from(format!("{}{} {}", icon, padding, label))
+                    }
+                    None => Cow::from(label),
+                };
+
+                (key, Text::styled(text, style))
+            })
+            .collect()
+    }
 
-        keys.insert(
-            ReadoutKey::ProcessorLoad,
-            Text::styled(&self.theme.keys.cpu_load, color_style),
-        );
+    /// Applies the `[styles.<readout>]` of the theme to the value of a readout.
+    fn style_value<'t>(&self, key: ReadoutKey, value: &Text<'t>) -> Text<'t> {
+        let mut value = value.to_owned();
+        if let Some(readout_style) = self.theme.get_readout_style(key) {
+            for span in value.lines.iter_mut().flat_map(|line| line.0.iter_mut()) {
+                if let (None, Some(color)) = (span.style.fg, &readout_style.value_color) {
+                    span.style.fg = Some(color.0);
+                }
+                span.style = span.style.add_modifier(readout_style.modifiers());
+            }
+        }
 
-        keys.insert(
-            ReadoutKey::Uptime,
-            Text::styled(&self.theme.keys.uptime, color_style),
-        );
+        value
+    }
 
-        keys
+    /// Returns the value of a readout as it is drawn, styled and cut off at the maximum width
+    /// of the theme.
+    fn value_text<'t>(&self, key: ReadoutKey, value: &Text<'t>) -> Text<'t> {
+        let value = self.style_value(key, value);
+        match self.theme.get_max_value_width() {
+            Some(max_width) => truncate(value, max_width),
+            None => value,
+        }
     }
 
+    /// Returns the width of the widest key in columns, icons and other wide glyphs included.
     fn get_max_key_width(keys: &HashMap<ReadoutKey, Text>) -> usize {
-        keys.iter().map(|i| i.1.width()).max().unwrap()
+        keys.values()
+            .flat_map(|key| key.lines.iter())
+            .map(|line| line.0.iter().map(|span| span.content.width()).sum())
+            .max()
+            .unwrap_or(0)
     }
 
+    /// Draws the block around the content, within `area` so that it stays whole when the
+    /// content doesn't fit.
     fn render_block(
         block: Option<Block<'a>>,
         buf: &mut Buffer,
-        x: u16,
-        y: u16,
+        area: Rect,
         content_height: u16,
         content_width: u16,
         margin: &Margin,
     ) {
         if let Some(block) = block {
-            block.render(
-                Rect {
-                    x,
-                    y,
-                    width: content_width + 2 + margin.horizontal * 2,
-                    height: content_height + 2 + margin.vertical * 2,
-                },
-                buf,
-            );
+            let insets = block_insets(&block, margin);
+            let area = Rect {
+                width: (content_width + insets.width).min(area.width),
+                height: (content_height + insets.height).min(area.height),
+                ..area
+            };
+
+            if let Some(visible_area) = Self::clip(area, buf.area) {
+                block.render(visible_area, buf);
+            }
         }
     }
 
@@ -330,15 +647,28 @@ impl<'a> ReadoutList<'a> {
     fn create_item_constraints(
         &self,
         max_key_width: usize,
+        key: &Text,
         themed_separator: &Text,
         readout_data: &Text,
     ) -> Vec<u16> {
-        let mut values = vec![
-            max_key_width as u16 + self.theme.get_spacing() as u16,
-            themed_separator.width() as u16,
-            self.theme.get_spacing() as u16,
-            readout_data.width() as u16,
-        ];
+        let spacing = self.theme.get_spacing() as u16;
+        let separator_width = themed_separator.width() as u16;
+        let key_width = match self.theme.get_key_alignment() {
+            KeyAlignment::None => key.width(),
+            _ => max_key_width,
+        } as u16;
+        let value_width = match self.theme.get_max_value_width() {
+            Some(max_width) => readout_data.width().min(max_width),
+            None => readout_data.width(),
+        } as u16;
+
+        let mut values = match self.theme.get_separator_position() {
+            // The separator is drawn along with the key, its own column is left empty.
+            SeparatorPosition::Key => vec![key_width + separator_width, 0, spacing, value_width],
+            SeparatorPosition::Column => {
+                vec![key_width + spacing, separator_width, spacing, value_width]
+            }
+        };
 
         if self.theme.get_padding() > 0 {
             values.insert(0, self.theme.get_padding() as u16)
@@ -371,3 +701,142 @@ impl<'a> ReadoutList<'a> {
         layout
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(toml: &str) -> Theme {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn readouts(keys: &[ReadoutKey]) -> Vec<Readout<'static>> {
+        keys.iter().map(|key| Readout::new(*key, "value")).collect()
+    }
+
+    fn titles(columns: &[Vec<Section>]) -> Vec<Vec<Option<String>>> {
+        columns
+            .iter()
+            .map(|column| column.iter().map(|s| s.title.map(String::from)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn truncate_keeps_lines_that_fit() {
+        let text = truncate(Text::raw("日本語"), 6);
+        assert_eq!(text.lines[0].width(), 6);
+        assert_eq!(text.lines[0].0[0].content, "日本語");
+    }
+
+    #[test]
+    fn truncate_does_not_split_wide_characters() {
+        // Only one column is left before the ellipsis, which "本" doesn't fit in.
+        let text = truncate(Text::raw("日本語"), 4);
+        let line: String = text.lines[0].0.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(line, "日…");
+        assert_eq!(text.lines[0].width(), 3);
+
+        let text = truncate(Text::raw("日本語"), 5);
+        let line: String = text.lines[0].0.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(line, "日本…");
+    }
+
+    #[test]
+    fn truncate_ends_with_the_style_of_the_cut_span() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let text = truncate(
+            Text::from(Spans::from(vec![
+                Span::raw("ab"),
+                Span::styled("cdef", bold),
+            ])),
+            4,
+        );
+        let ellipsis = text.lines[0].0.last().unwrap();
+        assert_eq!(ellipsis.content, "…");
+        assert_eq!(ellipsis.style, bold);
+    }
+
+    #[test]
+    fn block_insets_count_borders_title_and_margin() {
+        use tui::widgets::Borders;
+
+        let margin = Margin {
+            horizontal: 60,
+            vertical: 50,
+        };
+        let block = Block::default().borders(Borders::ALL);
+        assert_eq!(block_insets(&block, &margin), Rect::new(61, 51, 122, 102));
+
+        let block = Block::default().borders(Borders::LEFT).title("Title");
+        assert_eq!(block_insets(&block, &margin), Rect::new(61, 51, 121, 101));
+    }
+
+    #[test]
+    fn flow_columns_balances_sections() {
+        let theme = theme(
+            r#"
+            [groups]
+            columns = 2
+
+            [[groups.sections]]
+            title = "A"
+            readouts = ["host", "kernel", "distro", "uptime"]
+
+            [[groups.sections]]
+            title = "B"
+            readouts = ["cpu", "memory"]
+
+            [[groups.sections]]
+            title = "C"
+            readouts = ["shell", "terminal"]
+            "#,
+        );
+        let list = ReadoutList::new(
+            readouts(&[
+                ReadoutKey::Host,
+                ReadoutKey::Kernel,
+                ReadoutKey::Distribution,
+                ReadoutKey::Uptime,
+                ReadoutKey::Processor,
+                ReadoutKey::Memory,
+                ReadoutKey::Shell,
+                ReadoutKey::Terminal,
+            ]),
+            &theme,
+        )
+        .columns(2);
+
+        let a = Some(String::from("A"));
+        let b = Some(String::from("B"));
+        let c = Some(String::from("C"));
+        assert_eq!(titles(&list.flow_columns()), vec![vec![a], vec![b, c]]);
+    }
+
+    #[test]
+    fn flow_columns_keeps_to_the_allowed_columns() {
+        let theme = theme(
+            r#"
+            [[groups.sections]]
+            title = "A"
+            readouts = ["host"]
+
+            [[groups.sections]]
+            title = "B"
+            readouts = ["kernel"]
+            "#,
+        );
+        let keys = [ReadoutKey::Host, ReadoutKey::Kernel, ReadoutKey::Shell];
+
+        let list = ReadoutList::new(readouts(&keys), &theme);
+        assert_eq!(list.flow_columns().len(), 1);
+
+        // The readouts that aren't in a group make up a last, untitled section.
+        let list = ReadoutList::new(readouts(&keys), &theme).columns(5);
+        let a = Some(String::from("A"));
+        let b = Some(String::from("B"));
+        assert_eq!(
+            titles(&list.flow_columns()),
+            vec![vec![a], vec![b], vec![None]]
+        );
+    }
+}